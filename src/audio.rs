@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{
+    combine::Harvested,
+    difficulty::Difficulty,
+    enemy::{EnemyKilled, PlayerHit},
+    screens::game_over::NameEntry,
+    turret::{ItemCollected, Shot},
+    GameState,
+};
+
+/// Looping music played while the pressure is low
+struct CalmMusic;
+
+/// Looping music layered over [`CalmMusic`] as the pressure rises
+struct IntenseMusic;
+
+/// Sound effects
+struct Effects;
+
+/// Volume of each channel, between 0 and 1 (`M` toggles `muted`)
+#[derive(Debug, Clone)]
+pub struct AudioSettings {
+    pub music: f64,
+    pub effects: f64,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            music: 0.5,
+            effects: 0.7,
+            muted: false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct AssetTable {
+    harvest: Handle<AudioSource>,
+    shot: Handle<AudioSource>,
+    explosion: Handle<AudioSource>,
+    pickup: Handle<AudioSource>,
+    hit: Handle<AudioSource>,
+    game_over: Handle<AudioSource>,
+    calm_music: Handle<AudioSource>,
+    intense_music: Handle<AudioSource>,
+}
+
+/// Plays the sounds of the game
+///
+/// Must be added after the [`bevy_kira_audio::AudioPlugin`], which disables itself when there is no
+/// audio device, and after the game plugin whose events it listens to.
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetTable>()
+            .init_resource::<AudioSettings>()
+            .add_audio_channel::<CalmMusic>()
+            .add_audio_channel::<IntenseMusic>()
+            .add_audio_channel::<Effects>()
+            .add_startup_system(Self::load_assets)
+            .add_enter_system(GameState::Ready, Self::stop_music)
+            .add_exit_system(GameState::Ready, Self::start_music)
+            .add_enter_system(GameState::Paused, Self::pause_music)
+            .add_exit_system(GameState::Paused, Self::resume_music)
+            .add_enter_system(GameState::GameOver, Self::game_over)
+            // `M` is a letter of the name being typed then
+            .add_system(Self::toggle_mute.run_unless_resource_exists::<NameEntry>())
+            .add_system(Self::mix)
+            .add_system(Self::play_effects.run_in_state(GameState::Playing));
+    }
+}
+
+impl Plugin {
+    fn toggle_mute(keys: Res<Input<KeyCode>>, mut settings: ResMut<AudioSettings>) {
+        if keys.just_pressed(KeyCode::M) {
+            settings.muted = !settings.muted;
+        }
+    }
+
+    /// Sets the channel volumes, crossfading the music layers according to the difficulty
+    fn mix(
+        settings: Res<AudioSettings>,
+        difficulty: Res<Difficulty>,
+        calm: Res<AudioChannel<CalmMusic>>,
+        intense: Res<AudioChannel<IntenseMusic>>,
+        effects: Res<AudioChannel<Effects>>,
+        mut applied: Local<Option<[f64; 3]>>,
+    ) {
+        let (music, sfx) = if settings.muted {
+            (0.0, 0.0)
+        } else {
+            (settings.music, settings.effects)
+        };
+        let intensity = f64::from(difficulty.intensity);
+        let volumes = [music * (1.0 - intensity), music * intensity, sfx];
+
+        let unchanged = applied.map_or(false, |previous| {
+            previous
+                .iter()
+                .zip(volumes)
+                .all(|(previous, volume)| (previous - volume).abs() < 0.01)
+        });
+        if unchanged {
+            return;
+        }
+        *applied = Some(volumes);
+        calm.set_volume(volumes[0]);
+        intense.set_volume(volumes[1]);
+        effects.set_volume(volumes[2]);
+    }
+
+    fn start_music(
+        assets: Res<AssetTable>,
+        calm: Res<AudioChannel<CalmMusic>>,
+        intense: Res<AudioChannel<IntenseMusic>>,
+    ) {
+        let fade_in = AudioTween::linear(Duration::from_secs(1));
+        calm.stop();
+        intense.stop();
+        calm.play(assets.calm_music.clone())
+            .looped()
+            .fade_in(fade_in.clone());
+        intense
+            .play(assets.intense_music.clone())
+            .looped()
+            .fade_in(fade_in);
+    }
+
+    fn stop_music(calm: Res<AudioChannel<CalmMusic>>, intense: Res<AudioChannel<IntenseMusic>>) {
+        calm.stop();
+        intense.stop();
+    }
+
+    fn pause_music(calm: Res<AudioChannel<CalmMusic>>, intense: Res<AudioChannel<IntenseMusic>>) {
+        calm.pause();
+        intense.pause();
+    }
+
+    fn resume_music(calm: Res<AudioChannel<CalmMusic>>, intense: Res<AudioChannel<IntenseMusic>>) {
+        calm.resume();
+        intense.resume();
+    }
+
+    fn game_over(
+        assets: Res<AssetTable>,
+        calm: Res<AudioChannel<CalmMusic>>,
+        intense: Res<AudioChannel<IntenseMusic>>,
+        effects: Res<AudioChannel<Effects>>,
+    ) {
+        let fade_out = AudioTween::linear(Duration::from_millis(500));
+        calm.stop().fade_out(fade_out.clone());
+        intense.stop().fade_out(fade_out);
+        effects.play(assets.game_over.clone());
+    }
+
+    fn play_effects(
+        assets: Res<AssetTable>,
+        effects: Res<AudioChannel<Effects>>,
+        mut harvests: EventReader<Harvested>,
+        mut shots: EventReader<Shot>,
+        mut kills: EventReader<EnemyKilled>,
+        mut items: EventReader<ItemCollected>,
+        mut hits: EventReader<PlayerHit>,
+    ) {
+        // Several events of the same kind in a frame play a single sound
+        let sounds = [
+            (harvests.iter().count(), &assets.harvest),
+            (shots.iter().count(), &assets.shot),
+            (kills.iter().count(), &assets.explosion),
+            (items.iter().count(), &assets.pickup),
+            (hits.iter().count(), &assets.hit),
+        ];
+        for (count, sound) in sounds {
+            if count > 0 {
+                effects.play(sound.clone());
+            }
+        }
+    }
+
+    fn load_assets(mut table: ResMut<AssetTable>, server: Res<AssetServer>) {
+        table.harvest = server.load("sounds/harvest.wav");
+        table.shot = server.load("sounds/shot.wav");
+        table.explosion = server.load("sounds/explosion.wav");
+        table.pickup = server.load("sounds/pickup.wav");
+        table.hit = server.load("sounds/hit.wav");
+        table.game_over = server.load("sounds/game_over.wav");
+        table.calm_music = server.load("sounds/music_calm.wav");
+        table.intense_music = server.load("sounds/music_intense.wav");
+    }
+}
@@ -3,7 +3,11 @@ use std::f32::consts::PI;
 use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 
-use crate::{despawn::despawn, field::Field, GameState};
+use crate::{
+    despawn::despawn,
+    field::{self, Field},
+    GameState,
+};
 
 #[derive(Debug, Default, Component)]
 struct Barrier;
@@ -21,7 +25,7 @@ impl bevy::prelude::Plugin for Plugin {
         app.init_resource::<AssetTable>()
             .add_startup_system(Self::load_assets)
             .add_enter_system(GameState::Ready, despawn::<Barrier>)
-            .add_enter_system(GameState::Ready, Self::spawn);
+            .add_enter_system(GameState::Ready, Self::spawn.after(field::Generate));
     }
 }
 
@@ -136,9 +140,13 @@ impl Plugin {
 
     fn load_assets(
         asset_server: Res<AssetServer>,
-        mut textures: ResMut<Assets<TextureAtlas>>,
+        textures: Option<ResMut<Assets<TextureAtlas>>>,
         mut assets: ResMut<AssetTable>,
     ) {
+        let mut textures = match textures {
+            Some(textures) => textures,
+            None => return,
+        };
         assets.border = textures.add(TextureAtlas::from_grid(
             asset_server.load("sprites/barrier.png"),
             Vec2::splat(32.0),
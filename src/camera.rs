@@ -1,24 +1,49 @@
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    render::camera::{CameraUpdateSystem, ScalingMode},
+};
 
 use crate::field::Field;
 
+/// Room kept above the field for the HUD, in cells
+const HUD_MARGIN: f32 = 2.0;
+
 #[derive(Default)]
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_startup_system(Self::spawn);
+        app.add_startup_system(Self::spawn)
+            .add_system_to_stage(CoreStage::PostUpdate, Self::fit.before(CameraUpdateSystem));
     }
 }
 
 impl Plugin {
-    fn spawn(mut commands: Commands, field: Res<Field>) {
-        let mut camera = Camera2dBundle::default();
-        camera.transform.translation = field
-            .center()
-            .as_vec2()
-            .extend(camera.transform.translation.z);
-        camera.transform.scale = Vec3::new(0.02, 0.02, 1.0);
-        commands.spawn_bundle(camera);
+    fn spawn(mut commands: Commands) {
+        commands.spawn_bundle(Camera2dBundle::default());
+    }
+
+    /// Frames the field and its barrier, whatever the size of both the field and the window
+    ///
+    /// The projection keeps this area visible when the window is resized, leaving the extra space
+    /// on the sides or above and below it.
+    fn fit(
+        field: Res<Field>,
+        mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+        mut fitted: Local<Option<(u32, u32)>>,
+    ) {
+        let size = (field.width, field.height);
+        if *fitted == Some(size) {
+            return;
+        }
+        for (mut transform, mut projection) in &mut cameras {
+            let middle = field.middle() + Vec2::Y * HUD_MARGIN / 2.0;
+            transform.translation = middle.extend(transform.translation.z);
+            projection.scaling_mode = ScalingMode::Auto {
+                min_width: field.width as f32 + 2.0,
+                min_height: field.height as f32 + 2.0 + HUD_MARGIN,
+            };
+            *fitted = Some(size);
+        }
     }
 }
@@ -0,0 +1,11 @@
+/// Returns the value following `--<name>` in the command line arguments
+pub fn arg(name: &str) -> Option<String> {
+    let flag = format!("--{name}");
+    std::env::args().skip_while(|a| *a != flag).nth(1)
+}
+
+/// Whether `--<name>` is in the command line arguments
+pub fn flag(name: &str) -> bool {
+    let flag = format!("--{name}");
+    std::env::args().any(|a| a == flag)
+}
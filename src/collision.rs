@@ -0,0 +1,127 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{
+    simulation,
+    spatial::{Indexing, SpatialGrid, SpatialGridApp},
+};
+
+/// Shape of an entity, for the collision detection
+///
+/// Boxes are axis-aligned, whatever the rotation of the entity is.
+#[derive(Debug, Clone, Copy, Component)]
+pub enum Collider {
+    Circle { radius: f32 },
+    Box { half_size: Vec2 },
+}
+
+impl Collider {
+    /// Radius of the smallest circle containing the shape
+    fn extent(&self) -> f32 {
+        match *self {
+            Self::Circle { radius } => radius,
+            Self::Box { half_size } => half_size.length(),
+        }
+    }
+
+    fn overlaps(&self, at: Vec2, other: &Self, other_at: Vec2) -> bool {
+        match (*self, *other) {
+            (
+                Self::Circle { radius },
+                Self::Circle {
+                    radius: other_radius,
+                },
+            ) => at.distance_squared(other_at) < (radius + other_radius).powi(2),
+            (Self::Circle { radius }, Self::Box { half_size }) => {
+                let closest = at.clamp(other_at - half_size, other_at + half_size);
+                at.distance_squared(closest) < radius * radius
+            }
+            (Self::Box { .. }, Self::Circle { .. }) => other.overlaps(other_at, self, at),
+            (
+                Self::Box { half_size },
+                Self::Box {
+                    half_size: other_half_size,
+                },
+            ) => {
+                let distance = (at - other_at).abs();
+                distance.cmplt(half_size + other_half_size).all()
+            }
+        }
+    }
+}
+
+/// Sent at each simulation step during which an entity having the component `A` overlaps an
+/// entity having the component `B`
+#[derive(Debug)]
+pub struct Collision<A, B> {
+    pub a: Entity,
+    pub b: Entity,
+    marker: PhantomData<fn() -> (A, B)>,
+}
+
+pub trait CollisionApp {
+    /// Detects the collisions between entities with `A` and entities with `B`, and sends them as
+    /// [`Collision<A, B>`] events before the gameplay systems of each simulation step run
+    ///
+    /// Both kinds of entity must have a [`Collider`].
+    fn add_collision<A: Component, B: Component>(&mut self) -> &mut Self;
+}
+
+impl CollisionApp for App {
+    fn add_collision<A: Component, B: Component>(&mut self) -> &mut Self {
+        if !self.world.contains_resource::<SpatialGrid<B>>() {
+            self.add_spatial_grid::<B>();
+        }
+        self.add_event::<Collision<A, B>>()
+            .add_fixed_timestep_system(
+                simulation::LABEL,
+                simulation::INPUT,
+                detect::<A, B>.after(Indexing),
+            )
+    }
+}
+
+fn detect<A: Component, B: Component>(
+    grid: Res<SpatialGrid<B>>,
+    entities: Query<(Entity, &Transform, &Collider), With<A>>,
+    others: Query<&Collider, With<B>>,
+    mut collisions: EventWriter<Collision<A, B>>,
+) {
+    let reach = others.iter().map(Collider::extent).fold(0.0, f32::max);
+    for (entity, transform, collider) in &entities {
+        let at = transform.translation.truncate();
+        for (other, other_at) in grid.query(at, collider.extent() + reach) {
+            if other == entity {
+                continue;
+            }
+            let overlaps = others.get(other).map_or(false, |other_collider| {
+                collider.overlaps(at, other_collider, other_at)
+            });
+            if overlaps {
+                collisions.send(Collision {
+                    a: entity,
+                    b: other,
+                    marker: PhantomData,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circle_overlaps_box_only_when_touching_its_edge() {
+        let circle = Collider::Circle { radius: 0.2 };
+        let cell = Collider::Box {
+            half_size: Vec2::splat(0.5),
+        };
+        assert!(circle.overlaps(Vec2::new(0.65, 0.0), &cell, Vec2::ZERO));
+        assert!(cell.overlaps(Vec2::ZERO, &circle, Vec2::new(0.65, 0.0)));
+        assert!(!circle.overlaps(Vec2::new(0.65, 0.65), &cell, Vec2::ZERO));
+    }
+}
@@ -4,18 +4,45 @@ use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 
 use crate::{
+    collision::Collider,
+    controls::Controls,
+    crop::Species,
     despawn::despawn,
-    field::{Cell, Field, Position},
+    field::{self, Cell, Field, Position},
+    health::{Health, HealthSettings},
+    simulation::{self, Interpolated},
     GameState,
 };
 
 #[derive(Debug, Clone, Copy, Component)]
 pub struct Harvester;
 
-pub struct Harvested;
+#[derive(Debug, Default)]
+struct AssetTable {
+    combine: Handle<TextureAtlas>,
+}
+
+/// Crop harvested on a cell, sent once per cell
+#[derive(Debug, Clone, Copy)]
+pub struct Harvested {
+    pub position: Position,
+    /// The cell
+    pub entity: Entity,
+    pub species: Species,
+    pub level: u8,
+    /// The harvester
+    pub by: Entity,
+}
+
+impl Harvested {
+    /// Yield of the harvested crop, which is what it scores
+    pub fn total_yield(&self) -> f32 {
+        self.species.yield_per_level() * self.level as f32
+    }
+}
 
 #[derive(Debug, Clone, Component)]
-struct Movement {
+pub struct Movement {
     direction: IVec2,
     control: Option<IVec2>,
     timer: Timer,
@@ -36,6 +63,19 @@ impl Movement {
         pos.as_vec2() + (self.direction.as_vec2() * (self.timer.elapsed_secs() * SPEED as f32))
     }
 
+    /// World coordinates of the combine at `pos` in `seconds`, if it takes the turn it was asked
+    /// to at the next cell and then goes straight on, ignoring the obstacles
+    pub fn predict(&self, pos: Position, seconds: f32) -> Vec2 {
+        let speed = SPEED as f32;
+        let to_next_cell = self.timer.remaining_secs();
+        if seconds < to_next_cell {
+            return self.world_coord(pos) + self.direction.as_vec2() * seconds * speed;
+        }
+        let next_cell = *pos + self.direction;
+        let direction = self.control.unwrap_or(self.direction);
+        next_cell.as_vec2() + direction.as_vec2() * (seconds - to_next_cell) * speed
+    }
+
     fn update(&mut self, pos: &mut Position, delta: Duration) {
         self.timer.tick(delta);
         if self.timer.just_finished() {
@@ -53,14 +93,18 @@ pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<Harvested>()
+        app.init_resource::<AssetTable>()
+            .add_event::<Harvested>()
+            .add_startup_system(Self::load_assets)
             .add_enter_system(GameState::Ready, despawn::<Harvester>)
-            .add_enter_system(GameState::Ready, Self::spawn)
-            .add_system_set(
+            .add_enter_system(GameState::Ready, Self::spawn.after(field::Generate))
+            .add_fixed_timestep_system_set(
+                simulation::LABEL,
+                simulation::UPDATE,
                 ConditionSet::new()
                     .run_in_state(GameState::Playing)
                     .with_system(Self::control)
-                    .with_system(Self::reverse_in_front_of_barrier)
+                    .with_system(Self::bounce)
                     .with_system(Self::movement)
                     .with_system(Self::harvest)
                     .with_system(Self::rotate_sprite)
@@ -72,67 +116,66 @@ impl bevy::prelude::Plugin for Plugin {
 impl Plugin {
     fn harvest(
         field: ResMut<Field>,
-        combine: Query<&Transform, With<Harvester>>,
-        mut cells: Query<&mut Cell>,
+        combine: Query<(Entity, &Transform), With<Harvester>>,
+        mut cells: Query<(&mut Cell, &Position, &Species)>,
         mut events: EventWriter<Harvested>,
     ) {
-        for position in combine
-            .iter()
-            .filter_map(|&t| field.get_at(t.translation.truncate()))
-        {
-            if let Ok(mut cell) = cells.get_mut(position) {
+        for (by, transform) in &combine {
+            let entity = match field.get_at(transform.translation.truncate()) {
+                Some(entity) => entity,
+                None => continue,
+            };
+            if let Ok((mut cell, &position, &species)) = cells.get_mut(entity) {
                 if let Cell::Crop { level } = *cell {
-                    for _ in 0..level {
-                        events.send(Harvested);
-                    }
+                    events.send(Harvested {
+                        position,
+                        entity,
+                        species,
+                        level,
+                        by,
+                    });
+                    *cell = Cell::Harvested;
                 }
-                *cell = Cell::Harvested;
             }
         }
     }
 
-    fn control(input: Res<Input<KeyCode>>, mut combine: Query<&mut Movement>) {
+    fn control(controls: Res<Controls>, mut combine: Query<&mut Movement>) {
+        let asked = match controls.steer {
+            Some(direction) => direction,
+            None => return,
+        };
         for mut movement in combine.iter_mut() {
-            let asked = if input.pressed(KeyCode::Up) || input.pressed(KeyCode::W) {
-                IVec2::Y
-            } else if input.pressed(KeyCode::Down) || input.pressed(KeyCode::S) {
-                -IVec2::Y
-            } else if input.pressed(KeyCode::Right) || input.pressed(KeyCode::D) {
-                IVec2::X
-            } else if input.pressed(KeyCode::Left) || input.pressed(KeyCode::A) {
-                -IVec2::X
-            } else {
-                return;
-            };
-
             movement.control = Some(asked);
         }
     }
 
-    fn reverse_in_front_of_barrier(
+    /// Bounces off the barrier and the obstacles, turning aside when going back is blocked too
+    fn bounce(
         mut combine: Query<(&mut Movement, &Position)>,
         field: Res<Field>,
+        cells: Query<&Cell>,
     ) {
         for (mut movement, position) in &mut combine {
-            if (position.x == 0 && movement.direction.x < 0)
-                || (position.x == field.width as i32 - 1 && movement.direction.x > 0)
-            {
-                movement.direction.x = -movement.direction.x;
+            let blocked =
+                |direction: IVec2| field.is_blocked(Position(**position + direction), &cells);
+            let direction = movement.direction;
+            if !blocked(direction) {
+                continue;
             }
-            if (position.y == 0 && movement.direction.y < 0)
-                || (position.y == field.height as i32 - 1 && movement.direction.y > 0)
+            let aside = IVec2::new(-direction.y, direction.x);
+            if let Some(free) = [-direction, aside, -aside]
+                .into_iter()
+                .find(|&d| !blocked(d))
             {
-                movement.direction.y = -movement.direction.y;
+                movement.direction = free;
             }
         }
     }
 
-    fn movement(
-        time: Res<Time>,
-        mut combine: Query<(&mut Transform, &mut Movement, &mut Position)>,
-    ) {
+    fn movement(mut combine: Query<(&mut Transform, &mut Movement, &mut Position)>) {
         for (mut transform, mut movement, mut pos) in combine.iter_mut() {
-            movement.update(&mut pos, time.delta());
+            movement.update(&mut pos, simulation::STEP);
             transform.translation = movement.world_coord(*pos).extend(transform.translation.z);
         }
     }
@@ -146,21 +189,14 @@ impl Plugin {
 
     fn spawn(
         mut commands: Commands,
-        asset_server: Res<AssetServer>,
-        mut textures: ResMut<Assets<TextureAtlas>>,
+        assets: Res<AssetTable>,
         field: Res<Field>,
+        health: Res<HealthSettings>,
     ) {
-        let texture_atlas = textures.add(TextureAtlas::from_grid(
-            asset_server.load("sprites/combine.png"),
-            Vec2::splat(32.0),
-            1,
-            1,
-        ));
-
-        let position = field.center();
+        let position = field.start;
         commands
             .spawn_bundle(SpriteSheetBundle {
-                texture_atlas,
+                texture_atlas: assets.combine.clone(),
                 transform: Transform::from_translation(position.as_vec2().extend(1.0)),
                 sprite: TextureAtlasSprite {
                     custom_size: Some(Vec2::ONE),
@@ -170,7 +206,27 @@ impl Plugin {
             })
             .insert(position)
             .insert(Harvester)
-            .insert(Movement::new(IVec2::X))
+            .insert(Collider::Circle { radius: 0.15 })
+            .insert(Movement::new(field.direction))
+            .insert(Health::new(health.hit_points))
+            .insert(Interpolated::default())
             .insert(Name::from("Combine"));
     }
+
+    fn load_assets(
+        mut table: ResMut<AssetTable>,
+        server: Res<AssetServer>,
+        textures: Option<ResMut<Assets<TextureAtlas>>>,
+    ) {
+        let mut textures = match textures {
+            Some(textures) => textures,
+            None => return,
+        };
+        table.combine = textures.add(TextureAtlas::from_grid(
+            server.load("sprites/combine.png"),
+            Vec2::splat(32.0),
+            1,
+            1,
+        ));
+    }
 }
@@ -0,0 +1,288 @@
+use std::{collections::BTreeMap, fmt::Display, path::PathBuf};
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{combine::Harvester, data, mouse::Cursor};
+
+/// Minimum tilt of a stick for it to be taken into account
+const DEAD_ZONE: f32 = 0.5;
+
+/// Distance from the combine at which the turret aims when using a gamepad
+const STICK_AIM_DISTANCE: f32 = 5.0;
+
+/// What the player asks for during the current frame
+///
+/// Gameplay systems read this instead of the input devices, so that the inputs can come from
+/// somewhere else (like a replay).
+#[derive(Debug, Clone, Default)]
+pub struct Controls {
+    pub steer: Option<IVec2>,
+    pub fire: bool,
+    pub aim: Vec2,
+    /// Whether pause has just been pressed
+    pub pause: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    SteerUp,
+    SteerDown,
+    SteerLeft,
+    SteerRight,
+    Fire,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::SteerUp,
+        Action::SteerDown,
+        Action::SteerLeft,
+        Action::SteerRight,
+        Action::Fire,
+        Action::Pause,
+    ];
+}
+
+/// An input that can trigger an [`Action`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    /// Keyboard and mouse bindings are replaced together, independently of the gamepad ones
+    pub fn is_gamepad(&self) -> bool {
+        matches!(self, Binding::Gamepad(_))
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{key:?}"),
+            Binding::Mouse(button) => write!(f, "Mouse {button:?}"),
+            Binding::Gamepad(button) => write!(f, "Pad {button:?}"),
+        }
+    }
+}
+
+/// Inputs triggering each [`Action`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bindings(BTreeMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Binding::*;
+
+        Self(BTreeMap::from([
+            (
+                Action::SteerUp,
+                vec![
+                    Key(KeyCode::W),
+                    Key(KeyCode::Up),
+                    Gamepad(GamepadButtonType::DPadUp),
+                ],
+            ),
+            (
+                Action::SteerDown,
+                vec![
+                    Key(KeyCode::S),
+                    Key(KeyCode::Down),
+                    Gamepad(GamepadButtonType::DPadDown),
+                ],
+            ),
+            (
+                Action::SteerLeft,
+                vec![
+                    Key(KeyCode::A),
+                    Key(KeyCode::Left),
+                    Gamepad(GamepadButtonType::DPadLeft),
+                ],
+            ),
+            (
+                Action::SteerRight,
+                vec![
+                    Key(KeyCode::D),
+                    Key(KeyCode::Right),
+                    Gamepad(GamepadButtonType::DPadRight),
+                ],
+            ),
+            (
+                Action::Fire,
+                vec![
+                    Mouse(MouseButton::Left),
+                    Gamepad(GamepadButtonType::RightTrigger2),
+                    Gamepad(GamepadButtonType::RightTrigger),
+                ],
+            ),
+            (
+                Action::Pause,
+                vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::Start)],
+            ),
+        ]))
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds `binding` to `action`, in place of the bindings of the same kind of device
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.0.entry(action).or_default();
+        bindings.retain(|b| b.is_gamepad() != binding.is_gamepad());
+        bindings.push(binding);
+    }
+}
+
+/// Where the [`Bindings`] customized in the settings screen are kept, `bindings.ron` in the user
+/// data directory by default, or `None` to always start with the default ones
+#[derive(Debug, Clone)]
+pub struct BindingsFile(pub Option<PathBuf>);
+
+/// State of the input devices, to check the [`Bindings`] against
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct Devices<'w, 's> {
+    keyboard: Res<'w, Input<KeyCode>>,
+    mouse: Res<'w, Input<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    buttons: Res<'w, Input<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    #[system_param(ignore)]
+    marker: std::marker::PhantomData<&'s ()>,
+}
+
+impl<'w, 's> Devices<'w, 's> {
+    fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keyboard.pressed(key),
+            Binding::Mouse(button) => self.mouse.pressed(button),
+            Binding::Gamepad(button) => self
+                .gamepads
+                .iter()
+                .any(|&gamepad| self.buttons.pressed(GamepadButton::new(gamepad, button))),
+        }
+    }
+
+    fn just_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keyboard.just_pressed(key),
+            Binding::Mouse(button) => self.mouse.just_pressed(button),
+            Binding::Gamepad(button) => self.gamepads.iter().any(|&gamepad| {
+                self.buttons
+                    .just_pressed(GamepadButton::new(gamepad, button))
+            }),
+        }
+    }
+
+    /// First input pressed during this frame, if any
+    pub fn any_just_pressed(&self) -> Option<Binding> {
+        let gamepad_button = || {
+            self.buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Gamepad(button.button_type))
+        };
+        self.keyboard
+            .get_just_pressed()
+            .next()
+            .map(|&key| Binding::Key(key))
+            .or_else(|| {
+                self.mouse
+                    .get_just_pressed()
+                    .next()
+                    .map(|&button| Binding::Mouse(button))
+            })
+            .or_else(gamepad_button)
+    }
+
+    /// Tilt of a stick of any gamepad, if beyond the [`DEAD_ZONE`]
+    fn stick(&self, x: GamepadAxisType, y: GamepadAxisType) -> Option<Vec2> {
+        self.gamepads.iter().find_map(|&gamepad| {
+            let tilt = Vec2::new(
+                self.axes.get(GamepadAxis::new(gamepad, x))?,
+                self.axes.get(GamepadAxis::new(gamepad, y))?,
+            );
+            (tilt.length() >= DEAD_ZONE).then_some(tilt)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, SystemLabel)]
+pub struct ReadControls;
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Controls>()
+            .init_resource::<Bindings>()
+            .insert_resource(BindingsFile(data::user_file("bindings.ron")))
+            .add_startup_system(Self::load_bindings)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                Self::read_devices.label(ReadControls).after(InputSystem),
+            );
+    }
+}
+
+impl Plugin {
+    fn load_bindings(mut bindings: ResMut<Bindings>, file: Res<BindingsFile>) {
+        let path = match &file.0 {
+            Some(path) if path.exists() => path,
+            _ => return,
+        };
+        match data::load_ron(path) {
+            Ok(loaded) => *bindings = loaded,
+            Err(err) => error!("Cannot load bindings from {}: {err}", path.display()),
+        }
+    }
+
+    /// Aims with the right stick of a gamepad when it is tilted, with the mouse when it moves
+    fn read_devices(
+        mut controls: ResMut<Controls>,
+        bindings: Res<Bindings>,
+        devices: Devices,
+        cursor: Res<Cursor>,
+        combines: Query<&Transform, With<Harvester>>,
+        mut last_cursor: Local<Vec2>,
+    ) {
+        let active = |action| bindings.get(action).iter().any(|&b| devices.pressed(b));
+        let steer = [
+            (Action::SteerUp, IVec2::Y),
+            (Action::SteerDown, -IVec2::Y),
+            (Action::SteerRight, IVec2::X),
+            (Action::SteerLeft, -IVec2::X),
+        ]
+        .into_iter()
+        .find_map(|(action, direction)| active(action).then_some(direction));
+        let stick_steer = || {
+            let tilt = devices.stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY)?;
+            Some(if tilt.x.abs() > tilt.y.abs() {
+                IVec2::X * tilt.x.signum() as i32
+            } else {
+                IVec2::Y * tilt.y.signum() as i32
+            })
+        };
+        controls.steer = steer.or_else(stick_steer);
+        controls.fire = active(Action::Fire);
+        controls.pause = bindings
+            .get(Action::Pause)
+            .iter()
+            .any(|&b| devices.just_pressed(b));
+
+        let stick_aim = devices.stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY);
+        if let (Some(tilt), Ok(combine)) = (stick_aim, combines.get_single()) {
+            controls.aim = combine.translation.truncate() + tilt.normalize() * STICK_AIM_DISTANCE;
+        } else if **cursor != *last_cursor {
+            controls.aim = **cursor;
+        }
+        *last_cursor = **cursor;
+    }
+}
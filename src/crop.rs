@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+
+use crate::turret::Round;
+
+/// Species of the crop growing on a cell, kept once the cell is harvested
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Component)]
+pub enum Species {
+    #[default]
+    Wheat,
+    Corn,
+    Pumpkin,
+}
+
+impl Species {
+    pub const ALL: [Species; 3] = [Species::Wheat, Species::Corn, Species::Pumpkin];
+
+    /// Species of the biome at a point, from the value of the biome noise there
+    pub fn from_noise_value(value: f64) -> Self {
+        if value < -0.15 {
+            Self::Corn
+        } else if value > 0.15 {
+            Self::Pumpkin
+        } else {
+            Self::Wheat
+        }
+    }
+
+    /// Character standing for the species in the level files
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'w' => Some(Self::Wheat),
+            'c' => Some(Self::Corn),
+            'p' => Some(Self::Pumpkin),
+            _ => None,
+        }
+    }
+
+    /// Score of each harvested level of crop
+    pub fn yield_per_level(&self) -> f32 {
+        match self {
+            Self::Wheat => 1.0,
+            Self::Corn => 1.5,
+            Self::Pumpkin => 2.5,
+        }
+    }
+
+    /// Round loaded in the turret for each harvested level of crop
+    pub fn round(&self) -> Round {
+        match self {
+            Self::Wheat => Round::Regular,
+            Self::Corn => Round::Piercing,
+            Self::Pumpkin => Round::Heavy,
+        }
+    }
+
+    /// Row of the species in `sprites/crop.png`, which has one column per level
+    pub fn sprite_row(&self) -> usize {
+        Self::ALL.iter().position(|s| s == self).unwrap_or_default()
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    env, fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{Asset, AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Loads game data (balance tables, levels, ...) written in RON
+///
+/// Each kind of data has its own double extension (like `.difficulty.ron`) so that the asset server
+/// knows which type to deserialize.
+struct RonLoader<T> {
+    extensions: &'static [&'static str],
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Asset + DeserializeOwned> AssetLoader for RonLoader<T> {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let asset: T = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
+
+pub trait RonAssetApp {
+    fn add_ron_asset<T: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self;
+}
+
+impl RonAssetApp for App {
+    fn add_ron_asset<T: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self {
+        self.add_asset::<T>().add_asset_loader(RonLoader::<T> {
+            extensions,
+            marker: PhantomData,
+        })
+    }
+}
+
+/// Path of a file in the data directory of the user, where the game keeps what must survive it
+pub fn user_file(name: &str) -> Option<PathBuf> {
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+    Some(data_dir.join("combine-vs-aliens").join(name))
+}
+
+/// Reads a value written in RON from a file
+pub fn load_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> anyhow::Result<T> {
+    Ok(ron::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Writes a value in RON to a file, creating its directory if needed
+pub fn save_ron<T: Serialize>(value: &T, path: impl AsRef<Path>) -> anyhow::Result<()> {
+    if let Some(dir) = path.as_ref().parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, ron::to_string(value)?)?;
+    Ok(())
+}
@@ -1,12 +1,18 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{simulation::STEP, GameState};
 
 #[derive(Debug, Clone, Component, Deref, DerefMut)]
 pub struct DespawnTimer(Timer);
 
 pub fn systems() -> SystemSet {
-    SystemSet::new().with_system(after_timeout)
+    ConditionSet::new()
+        .run_not_in_state(GameState::Paused)
+        .with_system(after_timeout)
+        .into()
 }
 
 pub fn despawn<C: Component>(mut commands: Commands, entities: Query<Entity, With<C>>) {
@@ -15,13 +21,9 @@ pub fn despawn<C: Component>(mut commands: Commands, entities: Query<Entity, Wit
     }
 }
 
-fn after_timeout(
-    mut commands: Commands,
-    mut bullets: Query<(Entity, &mut DespawnTimer)>,
-    time: Res<Time>,
-) {
+fn after_timeout(mut commands: Commands, mut bullets: Query<(Entity, &mut DespawnTimer)>) {
     for (entity, mut timer) in &mut bullets {
-        timer.tick(time.delta());
+        timer.tick(STEP);
         if timer.finished() {
             commands.entity(entity).despawn_recursive();
         }
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, reflect::TypeUuid};
+use iyes_loopless::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    data::RonAssetApp,
+    field::{Cell, Field},
+    simulation, GameState,
+};
+
+/// Difficulty reached at a given pressure (see [`Curve`])
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Keyframe {
+    pressure: f32,
+    spawn_interval: f32,
+    enemy_speed: f32,
+    spawn_count: f32,
+}
+
+/// Difficulty curve, loaded from `assets/balance/default.difficulty.ron`
+///
+/// The pressure is the elapsed play time in seconds, plus `scarcity` seconds for each percent of the
+/// field that does not hold crop anymore. Values are linearly interpolated between keyframes.
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "6ea0561e-e79c-4e07-a477-e866da61051b"]
+pub struct Curve {
+    scarcity: f32,
+    keyframes: Vec<Keyframe>,
+}
+
+impl Curve {
+    fn sample(&self, pressure: f32) -> Option<Keyframe> {
+        let after = self.keyframes.iter().position(|k| k.pressure > pressure);
+        match after {
+            Some(0) => self.keyframes.first().copied(),
+            Some(index) => {
+                let (from, to) = (self.keyframes[index - 1], self.keyframes[index]);
+                let t = (pressure - from.pressure) / (to.pressure - from.pressure);
+                Some(Keyframe {
+                    pressure,
+                    spawn_interval: from.spawn_interval
+                        + (to.spawn_interval - from.spawn_interval) * t,
+                    enemy_speed: from.enemy_speed + (to.enemy_speed - from.enemy_speed) * t,
+                    spawn_count: from.spawn_count + (to.spawn_count - from.spawn_count) * t,
+                })
+            }
+            None => self.keyframes.last().copied(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Difficulty {
+    elapsed: Duration,
+    pub spawn_interval: Duration,
+    pub enemy_speed: f32,
+    pub spawn_count: u32,
+    /// Progress along the curve, from 0 at its first keyframe to 1 at its last one
+    pub intensity: f32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self {
+            elapsed: Duration::ZERO,
+            spawn_interval: Duration::from_secs_f32(0.5),
+            enemy_speed: 3.0,
+            spawn_count: 1,
+            intensity: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct CurveHandle(Handle<Curve>);
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<Curve>(&["difficulty.ron"])
+            .init_resource::<Difficulty>()
+            .init_resource::<CurveHandle>()
+            .add_startup_system(Self::load_curve)
+            .add_enter_system(GameState::Ready, Self::reset)
+            .add_fixed_timestep_system(
+                simulation::LABEL,
+                simulation::UPDATE,
+                Self::update.run_in_state(GameState::Playing),
+            );
+    }
+}
+
+impl Plugin {
+    fn reset(mut difficulty: ResMut<Difficulty>) {
+        *difficulty = Difficulty::default();
+    }
+
+    fn update(
+        mut difficulty: ResMut<Difficulty>,
+        handle: Res<CurveHandle>,
+        curves: Res<Assets<Curve>>,
+        field: Res<Field>,
+        cells: Query<&Cell>,
+    ) {
+        difficulty.elapsed += simulation::STEP;
+        let curve = match curves.get(&handle.0) {
+            Some(curve) => curve,
+            None => return,
+        };
+
+        let crop = cells
+            .iter()
+            .filter(|c| matches!(c, Cell::Crop { .. }))
+            .count();
+        // Nothing to lose on a field without arable cells
+        let lost = match field.arable() {
+            0 => 0.0,
+            arable => 100.0 * (1.0 - crop as f32 / arable as f32),
+        };
+        let pressure = difficulty.elapsed.as_secs_f32() + curve.scarcity * lost;
+
+        if let Some(keyframe) = curve.sample(pressure) {
+            difficulty.spawn_interval = Duration::from_secs_f32(keyframe.spawn_interval.max(0.01));
+            difficulty.enemy_speed = keyframe.enemy_speed;
+            difficulty.spawn_count = keyframe.spawn_count.floor().max(1.0) as u32;
+        }
+        if let (Some(first), Some(last)) = (curve.keyframes.first(), curve.keyframes.last()) {
+            let range = (last.pressure - first.pressure).max(f32::EPSILON);
+            difficulty.intensity = ((pressure - first.pressure) / range).clamp(0.0, 1.0);
+        }
+    }
+
+    fn load_curve(mut handle: ResMut<CurveHandle>, server: Res<AssetServer>) {
+        handle.0 = server.load("balance/default.difficulty.ron");
+    }
+}
@@ -1,143 +1,636 @@
-use std::time::Duration;
+use std::{collections::BTreeMap, f32::consts::TAU, time::Duration};
 
-use bevy::prelude::*;
+use bevy::{prelude::*, reflect::TypeUuid};
 use iyes_loopless::prelude::*;
-use rand::{thread_rng, Rng};
+use serde::Deserialize;
 
-use crate::{combine::Harvester, despawn::despawn, field::Field, movement::Velocity, GameState};
+use crate::{
+    collision::{Collider, Collision, CollisionApp},
+    combine::{Harvester, Movement},
+    data::RonAssetApp,
+    despawn::{despawn, DespawnTimer},
+    difficulty::Difficulty,
+    field::{Cell, Field, Position},
+    health::Health,
+    movement::{self, Velocity},
+    simulation::{self, Interpolated},
+    spatial::{SpatialGrid, SpatialGridApp},
+    steering::{Orbiting, Steering, COHESION_RADIUS},
+    GameState,
+};
 
-const SPEED: f32 = 3.0;
+/// Radius of the collider of an enemy of size 1
+const RADIUS: f32 = 0.2;
+
+const PROJECTILE_SPEED: f32 = 6.0;
+
+/// Number of columns in `sprites/enemy.png`
+const ENEMY_SPRITES: usize = 6;
+
+/// Speed of the homing projectiles, slower so that they can be outrun by turning
+const HOMING_SPEED: f32 = 3.5;
 
 #[derive(Debug, Clone, Default)]
 struct AssetTable {
-    bird: Handle<TextureAtlas>,
+    enemy: Handle<TextureAtlas>,
+    projectile: Handle<TextureAtlas>,
 }
 
 #[derive(Debug, Clone, Copy, Component)]
 pub struct Enemy;
 
-#[derive(Debug, Deref, DerefMut)]
-struct SpawnTimer(Timer);
+/// How an enemy moves and attacks
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum Behaviour {
+    /// Flies at the combine
+    Chase,
+    /// Keeps about `distance` from the combine, shooting at it every `reload` seconds
+    Ranged {
+        distance: f32,
+        reload: f32,
+        #[serde(default)]
+        fire: Fire,
+    },
+}
+
+/// How a ranged enemy shoots at the combine
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum Fire {
+    /// One projectile straight at the combine
+    #[default]
+    Aimed,
+    /// `count` projectiles fanned out over `angle` radians around the direction of the combine
+    Spread { count: usize, angle: f32 },
+    /// One slower projectile turning toward the combine by up to `turn_rate` radians per second
+    Homing { turn_rate: f32 },
+}
+
+/// Kind of enemy, as described in the [`Catalogue`]
+#[derive(Debug, Clone, Deserialize, Component)]
+pub struct Kind {
+    /// Column of the sprite in `sprites/enemy.png`
+    sprite: usize,
+    /// Size of the sprite and the collider, relative to a cell
+    #[serde(default = "default_size")]
+    size: f32,
+    /// Relative to the speed of the difficulty curve
+    speed: f32,
+    hit_points: u32,
+    /// Added to the score when killed
+    pub score: f32,
+    /// Chances to be picked for the waves made up after the last one, relative to the other kinds
+    weight: f32,
+    /// Difficulty intensity from which the kind can be picked
+    #[serde(default)]
+    from_intensity: f32,
+    behaviour: Behaviour,
+    /// How it flies toward the combine and among the other enemies
+    #[serde(default)]
+    steering: Steering,
+    /// Kind and number of the enemies spawned when killed
+    #[serde(default)]
+    split: Option<(String, u32)>,
+    /// Items that may be dropped when killed, named after their weapon, with their chance
+    #[serde(default)]
+    drops: Vec<(String, f32)>,
+}
+
+fn default_size() -> f32 {
+    1.0
+}
+
+impl Kind {
+    /// Whether enemies come out of it when killed
+    pub fn splits(&self) -> bool {
+        self.split.is_some()
+    }
+
+    /// Weapon of the item dropped for a roll between 0 and 1, if any
+    pub fn drop(&self, roll: f32) -> Option<&str> {
+        let mut chance = 0.0;
+        self.drops.iter().find_map(|(weapon, weapon_chance)| {
+            chance += weapon_chance;
+            (roll < chance).then_some(weapon.as_str())
+        })
+    }
+}
+
+/// Enemy catalogue, loaded from `assets/balance/default.enemies.ron`
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "3b0a8f5e-8d2c-4f4e-a7f1-5c9e2d7b6a10"]
+pub struct Catalogue {
+    kinds: BTreeMap<String, Kind>,
+}
+
+impl Catalogue {
+    pub fn get(&self, name: &str) -> Option<&Kind> {
+        self.kinds.get(name)
+    }
+
+    /// Name of the kind to spawn for a roll between 0 and 1, among the ones available at
+    /// `intensity`
+    pub fn pick(&self, intensity: f32, roll: f32) -> Option<&str> {
+        let available = || {
+            self.kinds
+                .iter()
+                .filter(|(_, k)| k.from_intensity <= intensity)
+        };
+        let mut left = roll * available().map(|(_, k)| k.weight).sum::<f32>();
+        available()
+            .find(|(_, k)| {
+                left -= k.weight;
+                left < 0.0
+            })
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CatalogueHandle(pub Handle<Catalogue>);
+
+/// Shoots at the combine when the timer finishes
+#[derive(Debug, Clone, Component)]
+struct Gun {
+    timer: Timer,
+    fire: Fire,
+}
+
+/// Shot by a ranged enemy, hurting the combine like an enemy does and shot down by bullets
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Projectile;
+
+/// Turns a projectile toward the combine by up to `turn_rate` radians per second
+#[derive(Debug, Clone, Copy, Component)]
+struct Homing {
+    turn_rate: f32,
+}
+
+/// Pace at which an enemy eats the crop of the cell it is flying over
+#[derive(Debug, Clone, Component, Deref, DerefMut)]
+struct Appetite(Timer);
 
-impl Default for SpawnTimer {
+impl Default for Appetite {
     fn default() -> Self {
-        Self(Timer::new(Duration::from_secs_f32(0.5), true))
+        Self(Timer::new(Duration::from_secs_f32(0.4), true))
     }
 }
 
 pub struct PlayerHit;
 
+/// Asks for an enemy of the kind named `kind` in the [`Catalogue`] to be spawned at `position`
+pub struct SpawnEnemy {
+    pub kind: String,
+    pub position: Vec2,
+}
+
+pub struct EnemyKilled {
+    pub position: Vec2,
+    pub kind: Kind,
+}
+
 #[derive(Default)]
 pub struct Plugin;
 
-#[derive(Debug, Clone, Copy, SystemLabel)]
-struct Spawn;
-
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<AssetTable>()
-            .init_resource::<SpawnTimer>()
+        app.add_ron_asset::<Catalogue>(&["enemies.ron"])
+            .init_resource::<AssetTable>()
+            .init_resource::<CatalogueHandle>()
             .add_event::<PlayerHit>()
+            .add_event::<SpawnEnemy>()
+            .add_event::<EnemyKilled>()
+            .add_spatial_grid::<Enemy>()
+            .add_collision::<Enemy, Harvester>()
+            .add_collision::<Enemy, Cell>()
+            .add_collision::<Projectile, Harvester>()
+            .add_collision::<Projectile, Cell>()
             .add_startup_system(Self::load_assets)
-            .add_exit_system(GameState::GameOver, despawn::<Enemy>)
+            .add_startup_system(Self::load_catalogue)
+            .add_enter_system(GameState::Ready, despawn::<Enemy>)
+            .add_enter_system(GameState::Ready, despawn::<Projectile>)
             .add_enter_system(GameState::GameOver, Self::stop)
-            .add_system_set(
+            .add_fixed_timestep_system_set(
+                simulation::LABEL,
+                simulation::UPDATE,
                 ConditionSet::new()
                     .run_in_state(GameState::Playing)
-                    .with_system(Self::cool_down)
-                    .with_system(Self::spawn.run_if(Self::should_spawn))
                     .with_system(Self::aim)
+                    .with_system(Self::shoot)
+                    .with_system(Self::home)
                     .with_system(Self::hit_combine)
+                    .with_system(Self::hit_obstacle)
+                    .with_system(Self::trample)
+                    .into(),
+            )
+            // After the systems asking for enemies and killing them, so that they always spawn at
+            // the same step, before the next one checks whether any enemy is left
+            .add_fixed_timestep_system_set(
+                simulation::LABEL,
+                simulation::LATE,
+                ConditionSet::new()
+                    .run_in_state(GameState::Playing)
+                    .with_system(Self::spawn)
+                    .with_system(Self::split)
                     .into(),
             );
     }
 }
 
 impl Plugin {
-    fn stop(mut commands: Commands, enemies: Query<Entity, (With<Enemy>, With<Velocity>)>) {
+    fn stop(
+        mut commands: Commands,
+        enemies: Query<Entity, (Or<(With<Enemy>, With<Projectile>)>, With<Velocity>)>,
+    ) {
         for enemy in &enemies {
             commands.entity(enemy).remove::<Velocity>();
         }
     }
 
-    fn cool_down(mut timer: ResMut<SpawnTimer>, time: Res<Time>) {
-        timer.tick(time.delta());
-    }
-
-    fn should_spawn(timer: Res<SpawnTimer>) -> bool {
-        timer.just_finished()
-    }
-
+    /// Steers toward the combine, or away from it when ranged and too close, turning aside when an
+    /// obstacle is ahead
+    #[allow(clippy::type_complexity)]
     fn aim(
-        mut enemies: Query<(&Transform, &mut Velocity), With<Enemy>>,
-        combines: Query<&Transform, (With<Harvester>, Without<Enemy>)>,
+        difficulty: Res<Difficulty>,
+        field: Res<Field>,
+        cells: Query<&Cell>,
+        grid: Res<SpatialGrid<Enemy>>,
+        mut enemies: Query<
+            (
+                Entity,
+                &Transform,
+                &Kind,
+                &mut Velocity,
+                Option<&mut Orbiting>,
+            ),
+            With<Enemy>,
+        >,
+        combines: Query<(&Movement, &Position), (With<Harvester>, Without<Enemy>)>,
     ) {
-        let combine_transform = match combines.get_single() {
-            Ok(t) => t,
+        const LOOK_AHEAD: f32 = 0.8;
+        const DETOURS: [f32; 7] = [0.0, 0.5, -0.5, 1.0, -1.0, 1.5, -1.5];
+
+        let (movement, combine_position) = match combines.get_single() {
+            Ok(combine) => combine,
             Err(_) => {
                 error!("Combine not found");
                 return;
             }
         };
+        let predict = |seconds| movement.predict(*combine_position, seconds);
 
-        for (enemy_transform, mut enemy_velocity) in enemies.iter_mut() {
-            let separation =
-                combine_transform.translation.truncate() - enemy_transform.translation.truncate();
-            **enemy_velocity = separation.normalize() * SPEED;
+        for (entity, enemy_transform, kind, mut enemy_velocity, mut orbiting) in &mut enemies {
+            let position = enemy_transform.translation.truncate();
+            let speed = difficulty.enemy_speed * kind.speed;
+            let pursuit = kind.steering.pursue(
+                position,
+                speed,
+                predict,
+                orbiting.as_deref_mut(),
+                simulation::STEP,
+            );
+            let pursuit = match kind.behaviour {
+                Behaviour::Chase => pursuit,
+                Behaviour::Ranged { distance, .. } => {
+                    let offset = predict(0.0) - position;
+                    let length = offset.length();
+                    if length > distance {
+                        pursuit
+                    } else if length < distance * 0.75 {
+                        -offset.normalize_or_zero()
+                    } else {
+                        Vec2::ZERO
+                    }
+                }
+            };
+            let neighbours = grid
+                .query(position, COHESION_RADIUS)
+                .filter(|&(other, _)| other != entity)
+                .map(|(_, at)| at);
+            let direction =
+                (pursuit + kind.steering.flock(position, neighbours)).normalize_or_zero();
+            let is_free = |direction: Vec2| {
+                let ahead = field
+                    .get_at(position + direction * LOOK_AHEAD)
+                    .and_then(|entity| cells.get(entity).ok());
+                !ahead.map_or(false, Cell::is_obstacle)
+            };
+            let direction = DETOURS
+                .iter()
+                .map(|&angle| Vec2::new(angle.cos(), angle.sin()))
+                .map(|rotation| {
+                    Vec2::new(
+                        direction.x * rotation.x - direction.y * rotation.y,
+                        direction.x * rotation.y + direction.y * rotation.x,
+                    )
+                })
+                .find(|&d| is_free(d))
+                .unwrap_or(direction);
+            **enemy_velocity = direction * speed;
+        }
+    }
+
+    fn shoot(
+        mut commands: Commands,
+        assets: Res<AssetTable>,
+        mut enemies: Query<(&Transform, &mut Gun), With<Enemy>>,
+        combines: Query<&Transform, (With<Harvester>, Without<Enemy>)>,
+    ) {
+        let target = match combines.get_single() {
+            Ok(transform) => transform.translation.truncate(),
+            Err(_) => return,
+        };
+        for (transform, mut gun) in &mut enemies {
+            gun.timer.tick(simulation::STEP);
+            if !gun.timer.just_finished() {
+                continue;
+            }
+            let position = transform.translation.truncate();
+            let aim = Vec2::X.angle_between(target - position);
+            let angles: Vec<f32> = match gun.fire {
+                Fire::Spread { count, angle } => movement::fan(aim, angle, count).collect(),
+                Fire::Aimed | Fire::Homing { .. } => vec![aim],
+            };
+            for angle in angles {
+                let direction = Vec2::new(angle.cos(), angle.sin());
+                let mut projectile = commands.spawn_bundle(SpriteSheetBundle {
+                    transform: Transform::from_translation(position.extend(2.5))
+                        .with_rotation(Quat::from_axis_angle(Vec3::Z, angle)),
+                    texture_atlas: assets.projectile.clone(),
+                    sprite: TextureAtlasSprite {
+                        custom_size: Some(Vec2::splat(0.6)),
+                        color: Color::hex("b43c3c").unwrap(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+                projectile
+                    .insert(Projectile)
+                    .insert(Collider::Circle { radius: 0.15 })
+                    .insert(Interpolated::default())
+                    .insert(DespawnTimer::new(Duration::from_secs(5)))
+                    .insert(Name::from("Projectile"));
+                match gun.fire {
+                    Fire::Homing { turn_rate } => {
+                        projectile
+                            .insert(Velocity(direction * HOMING_SPEED))
+                            .insert(Homing { turn_rate });
+                    }
+                    Fire::Aimed | Fire::Spread { .. } => {
+                        projectile.insert(Velocity(direction * PROJECTILE_SPEED));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Turns the homing projectiles toward the combine
+    fn home(
+        mut projectiles: Query<(&mut Transform, &mut Velocity, &Homing)>,
+        combines: Query<&Transform, (With<Harvester>, Without<Homing>)>,
+    ) {
+        let target = match combines.get_single() {
+            Ok(transform) => transform.translation.truncate(),
+            Err(_) => return,
+        };
+        for (mut transform, mut velocity, homing) in &mut projectiles {
+            let position = transform.translation.truncate();
+            let max_turn = homing.turn_rate * simulation::STEP.as_secs_f32();
+            let turn = velocity
+                .angle_between(target - position)
+                .clamp(-max_turn, max_turn);
+            if turn.is_nan() {
+                continue;
+            }
+            let speed = velocity.length();
+            let angle = Vec2::X.angle_between(**velocity) + turn;
+            **velocity = Vec2::new(angle.cos(), angle.sin()) * speed;
+            transform.rotation = Quat::from_axis_angle(Vec3::Z, angle);
         }
     }
 
     fn hit_combine(
+        mut commands: Commands,
         mut events: EventWriter<PlayerHit>,
-        combines: Query<&GlobalTransform, With<Harvester>>,
-        enemies: Query<&GlobalTransform, With<Enemy>>,
+        mut collisions: EventReader<Collision<Enemy, Harvester>>,
+        mut shots: EventReader<Collision<Projectile, Harvester>>,
     ) {
-        for combine in &combines {
-            for enemy in &enemies {
-                let distance_squared = (combine.translation().truncate()
-                    - enemy.translation().truncate())
-                .length_squared();
-                if distance_squared < 0.1 {
-                    events.send(PlayerHit);
+        let hits = collisions
+            .iter()
+            .map(|c| c.a)
+            .chain(shots.iter().map(|c| c.a));
+        for hit in hits {
+            commands.entity(hit).despawn_recursive();
+            events.send(PlayerHit);
+        }
+    }
+
+    fn hit_obstacle(
+        mut commands: Commands,
+        mut collisions: EventReader<Collision<Projectile, Cell>>,
+        cells: Query<&Cell>,
+    ) {
+        for collision in collisions.iter() {
+            if cells.get(collision.b).map_or(false, Cell::is_obstacle) {
+                commands.entity(collision.a).despawn_recursive();
+            }
+        }
+    }
+
+    fn trample(
+        mut collisions: EventReader<Collision<Enemy, Cell>>,
+        mut enemies: Query<&mut Appetite>,
+        mut cells: Query<&mut Cell>,
+    ) {
+        for mut appetite in &mut enemies {
+            appetite.tick(simulation::STEP);
+        }
+        for collision in collisions.iter() {
+            let hungry = enemies
+                .get(collision.a)
+                .map_or(false, |appetite| appetite.just_finished());
+            if !hungry {
+                continue;
+            }
+            if let Ok(mut cell) = cells.get_mut(collision.b) {
+                if matches!(*cell, Cell::Crop { .. }) {
+                    cell.trample();
                 }
             }
         }
     }
 
-    fn spawn(mut commands: Commands, field: Res<Field>, assets: Res<AssetTable>) {
-        let mut rng = thread_rng();
-        let pos = match (rng.gen_bool(0.5), rng.gen_bool(0.5)) {
-            (true, true) => IVec2::new(-1, rng.gen_range(0..field.height) as i32),
-            (true, false) => IVec2::new(field.width as i32, rng.gen_range(0..field.height) as i32),
-            (false, true) => IVec2::new(rng.gen_range(0..field.width) as i32, -1),
-            (false, false) => IVec2::new(rng.gen_range(0..field.width) as i32, field.height as i32),
+    fn spawn(
+        mut commands: Commands,
+        mut requests: EventReader<SpawnEnemy>,
+        assets: Res<AssetTable>,
+        handle: Res<CatalogueHandle>,
+        catalogues: Res<Assets<Catalogue>>,
+    ) {
+        let catalogue = match catalogues.get(&handle.0) {
+            Some(catalogue) => catalogue,
+            None => return,
         };
+        for request in requests.iter() {
+            match catalogue.get(&request.kind) {
+                Some(kind) => Self::spawn_enemy(&mut commands, &assets, kind, request.position),
+                None => error!("Unknown enemy kind {}", request.kind),
+            }
+        }
+    }
 
-        commands
-            .spawn_bundle(SpriteSheetBundle {
-                transform: Transform::from_translation(pos.as_vec2().extend(3.0)),
-                texture_atlas: assets.bird.clone(),
-                sprite: TextureAtlasSprite {
-                    custom_size: Some(Vec2::ONE),
-                    ..Default::default()
-                },
+    /// Spawns the enemies that come out of the killed ones
+    fn split(
+        mut commands: Commands,
+        mut kills: EventReader<EnemyKilled>,
+        assets: Res<AssetTable>,
+        handle: Res<CatalogueHandle>,
+        catalogues: Res<Assets<Catalogue>>,
+    ) {
+        let catalogue = match catalogues.get(&handle.0) {
+            Some(catalogue) => catalogue,
+            None => return,
+        };
+        for killed in kills.iter() {
+            let (name, count) = match &killed.kind.split {
+                Some(split) => split,
+                None => continue,
+            };
+            let kind = match catalogue.get(name) {
+                Some(kind) => kind,
+                None => {
+                    error!("Unknown enemy kind {name}");
+                    continue;
+                }
+            };
+            for i in 0..*count {
+                let angle = TAU * i as f32 / *count as f32;
+                let offset = Vec2::new(angle.cos(), angle.sin()) * 0.4;
+                Self::spawn_enemy(&mut commands, &assets, kind, killed.position + offset);
+            }
+        }
+    }
+
+    fn spawn_enemy(commands: &mut Commands, assets: &AssetTable, kind: &Kind, position: Vec2) {
+        let mut enemy = commands.spawn_bundle(SpriteSheetBundle {
+            transform: Transform::from_translation(position.extend(3.0)),
+            texture_atlas: assets.enemy.clone(),
+            sprite: TextureAtlasSprite {
+                custom_size: Some(Vec2::splat(kind.size)),
+                index: kind.sprite,
                 ..Default::default()
-            })
+            },
+            ..Default::default()
+        });
+        enemy
             .insert(Enemy)
+            .insert(kind.clone())
+            .insert(Health::new(kind.hit_points))
+            .insert(Appetite::default())
+            .insert(Collider::Circle {
+                radius: RADIUS * kind.size,
+            })
+            .insert(Interpolated::default())
             .insert(Name::from("Enemy"))
             .insert(Velocity(Vec2::ZERO));
+        if let Behaviour::Ranged { reload, fire, .. } = kind.behaviour {
+            enemy.insert(Gun {
+                timer: Timer::new(Duration::from_secs_f32(reload), true),
+                fire,
+            });
+        }
+        if let Some(orbit) = &kind.steering.orbit {
+            enemy.insert(Orbiting::new(orbit));
+        }
+    }
+
+    fn load_catalogue(mut handle: ResMut<CatalogueHandle>, server: Res<AssetServer>) {
+        handle.0 = server.load("balance/default.enemies.ron");
     }
 
     fn load_assets(
         mut table: ResMut<AssetTable>,
         server: Res<AssetServer>,
-        mut textures: ResMut<Assets<TextureAtlas>>,
+        textures: Option<ResMut<Assets<TextureAtlas>>>,
     ) {
-        table.bird = textures.add(TextureAtlas::from_grid(
+        let mut textures = match textures {
+            Some(textures) => textures,
+            None => return,
+        };
+        table.enemy = textures.add(TextureAtlas::from_grid(
             server.load("sprites/enemy.png"),
             Vec2::splat(32.0),
+            ENEMY_SPRITES,
             1,
+        ));
+        table.projectile = textures.add(TextureAtlas::from_grid(
+            server.load("sprites/bullet.png"),
+            Vec2::splat(32.0),
             1,
+            1,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::turret::Weapons;
+
+    #[test]
+    fn default_catalogue_is_consistent() {
+        let catalogue: Catalogue =
+            ron::from_str(include_str!("../assets/balance/default.enemies.ron")).unwrap();
+        let weapons: Weapons =
+            ron::from_str(include_str!("../assets/balance/default.weapons.ron")).unwrap();
+        assert!(catalogue.pick(0.0, 0.0).is_some());
+        for kind in catalogue.kinds.values() {
+            assert!(kind.sprite < ENEMY_SPRITES);
+            if let Some((name, _)) = &kind.split {
+                assert!(catalogue.kinds.contains_key(name));
+            }
+            assert!(kind.drops.iter().map(|(_, chance)| chance).sum::<f32>() <= 1.0);
+            for (weapon, _) in &kind.drops {
+                assert!(weapons.get(weapon).is_some(), "unknown weapon {weapon}");
+            }
+        }
+    }
+
+    #[test]
+    fn picks_only_the_kinds_available_at_the_intensity() {
+        let catalogue: Catalogue = ron::from_str(
+            r#"(kinds: {
+                "early": (sprite: 0, speed: 1.0, hit_points: 1, score: 1.0, weight: 1.0,
+                    behaviour: Chase),
+                "late": (sprite: 1, speed: 1.0, hit_points: 1, score: 1.0, weight: 1.0,
+                    from_intensity: 0.5, behaviour: Chase),
+            })"#,
+        )
+        .unwrap();
+        for roll in [0.0, 0.5, 0.99] {
+            assert_eq!(catalogue.pick(0.0, roll), Some("early"));
+        }
+        assert_eq!(catalogue.pick(1.0, 0.99), Some("late"));
+    }
+
+    #[test]
+    fn ranged_enemies_fire_aimed_projectiles_by_default() {
+        let behaviour: Behaviour = ron::from_str("Ranged(distance: 6.0, reload: 2.5)").unwrap();
+        assert!(matches!(
+            behaviour,
+            Behaviour::Ranged {
+                fire: Fire::Aimed,
+                ..
+            }
+        ));
+        let behaviour: Behaviour =
+            ron::from_str("Ranged(distance: 6.0, reload: 2.5, fire: Spread(count: 3, angle: 0.5))")
+                .unwrap();
+        assert!(matches!(
+            behaviour,
+            Behaviour::Ranged {
+                fire: Fire::Spread { count: 3, .. },
+                ..
+            }
         ));
     }
 }
@@ -1,20 +1,73 @@
 use bevy::{prelude::*, utils::HashMap};
-use iyes_loopless::prelude::AppLooplessStateExt;
-use noise::{Fbm, NoiseFn};
+use iyes_loopless::prelude::*;
+use noise::{Fbm, NoiseFn, Seedable};
+use rand::Rng;
 
-use crate::{despawn, GameState};
+use crate::{
+    cli,
+    collision::Collider,
+    crop::Species,
+    despawn,
+    level::{Edge, Layout, Level, LevelHandle},
+    rng::{self, GameRng},
+    GameState,
+};
 
 #[derive(Debug, Clone)]
 pub struct Field {
     pub(crate) width: u32,
     pub(crate) height: u32,
+    /// Cell on which the combine starts
+    pub start: Position,
+    /// Initial heading of the combine
+    pub direction: IVec2,
+    /// Sides of the field from which enemies come
+    pub spawn_edges: Vec<Edge>,
+    /// Number of cells holding an obstacle
+    pub obstacles: u32,
     map: HashMap<Position, Entity>,
 }
 
-#[derive(Debug, Copy, Clone, Component)]
+/// Size of the field generated for the next runs (`--field <width>x<height>`)
+#[derive(Debug, Clone, Copy)]
+pub struct FieldSettings {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for FieldSettings {
+    fn default() -> Self {
+        Self {
+            width: 31,
+            height: 15,
+        }
+    }
+}
+
+impl FieldSettings {
+    fn parse(size: &str) -> Option<Self> {
+        let (width, height) = size.split_once('x')?;
+        let settings = Self {
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+        };
+        (settings.width > 0 && settings.height > 0).then_some(settings)
+    }
+}
+
+/// Content of a cell
+///
+/// A `Sprout` is a harvested cell growing back (see [`crate::regrowth`]), not ripe enough to be
+/// harvested yet.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Component)]
 pub enum Cell {
     Crop { level: u8 },
     Harvested,
+    Sprout,
+    Destroyed,
+    Rock,
+    Pond,
+    Tree,
 }
 
 impl Cell {
@@ -29,6 +82,44 @@ impl Cell {
         };
         Self::Crop { level }
     }
+
+    /// Whether the cell blocks the combine, enemies and bullets
+    pub fn is_obstacle(&self) -> bool {
+        matches!(self, Self::Rock | Self::Pond | Self::Tree)
+    }
+
+    /// Eat one level of crop, destroying the cell once there is nothing left
+    pub fn trample(&mut self) {
+        if let Self::Crop { level } = *self {
+            *self = if level > 1 {
+                Self::Crop { level: level - 1 }
+            } else {
+                Self::Destroyed
+            };
+        }
+    }
+
+    /// Whether the cell can still climb a growth stage, up to crop of level `max_level`
+    pub fn can_grow(&self, max_level: u8) -> bool {
+        match *self {
+            Self::Harvested | Self::Sprout => true,
+            Self::Crop { level } => level < max_level,
+            _ => false,
+        }
+    }
+
+    /// Climb one growth stage: harvested, sprout, then each level of crop
+    pub fn grow(&mut self, max_level: u8) {
+        if !self.can_grow(max_level) {
+            return;
+        }
+        *self = match *self {
+            Self::Harvested => Self::Sprout,
+            Self::Sprout => Self::Crop { level: 1 },
+            Self::Crop { level } => Self::Crop { level: level + 1 },
+            cell => cell,
+        };
+    }
 }
 
 #[derive(Component)]
@@ -39,15 +130,29 @@ impl Field {
         Self {
             width,
             height,
+            start: Position(IVec2::new((width / 2) as i32, (height / 2) as i32)),
+            direction: IVec2::X,
+            spawn_edges: Edge::ALL.to_vec(),
+            obstacles: 0,
             map: HashMap::new(),
         }
     }
 
-    pub fn center(&self) -> Position {
-        Position(IVec2::new(
-            (self.width / 2) as i32,
-            (self.height / 2) as i32,
-        ))
+    /// Number of cells that are not obstacles
+    pub fn arable(&self) -> u32 {
+        self.width * self.height - self.obstacles
+    }
+
+    /// Whether the cell at `position` is outside of the field or an obstacle
+    pub fn is_blocked(&self, position: Position, cells: &Query<&Cell>) -> bool {
+        self.get(position)
+            .and_then(|entity| cells.get(entity).ok())
+            .map_or(true, Cell::is_obstacle)
+    }
+
+    /// World coordinates of the middle of the field
+    pub fn middle(&self) -> Vec2 {
+        Vec2::new(self.width as f32 - 1.0, self.height as f32 - 1.0) / 2.0
     }
 
     pub fn get(&self, position: Position) -> Option<Entity> {
@@ -55,7 +160,7 @@ impl Field {
     }
 
     pub fn get_at(&self, world_coord: Vec2) -> Option<Entity> {
-        self.get(Position(world_coord.round().as_ivec2()))
+        self.get(Position::from_world(world_coord))
     }
 }
 
@@ -63,10 +168,22 @@ impl Field {
 #[cfg_attr(feature = "inspector", derive(bevy_inspector_egui::Inspectable))]
 pub struct Position(pub(crate) IVec2);
 
+impl Position {
+    /// Position of the cell containing the given world coordinates
+    pub fn from_world(coord: Vec2) -> Self {
+        Self(coord.round().as_ivec2())
+    }
+}
+
+/// Label of the system generating the field, when entering [`GameState::Ready`]
+#[derive(Debug, Clone, Copy, SystemLabel)]
+pub struct Generate;
+
 #[derive(Debug, Default)]
 struct AssetTable {
     crop: Handle<TextureAtlas>,
     harvested: Handle<TextureAtlas>,
+    obstacle: Handle<TextureAtlas>,
 }
 
 #[derive(Default)]
@@ -74,16 +191,23 @@ pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        let mut noise = Fbm::new();
-        noise.octaves = 4;
-
-        app.insert_resource(Field::new(31, 15))
+        let settings = match cli::arg("field") {
+            Some(size) => FieldSettings::parse(&size).unwrap_or_else(|| {
+                error!("Invalid field size {size}, expected <width>x<height>");
+                FieldSettings::default()
+            }),
+            None => FieldSettings::default(),
+        };
+        app.insert_resource(settings)
+            .insert_resource(Field::new(settings.width, settings.height))
             .init_resource::<AssetTable>()
-            .insert_resource(noise)
             .add_startup_system(Self::load_assets)
             .add_enter_system(GameState::Ready, despawn::despawn::<Cell>)
             .add_enter_system(GameState::Ready, despawn::despawn::<CellGroup>)
-            .add_enter_system(GameState::Ready, Self::spawn)
+            .add_enter_system(
+                GameState::Ready,
+                Self::spawn.label(Generate).after(rng::Reseed),
+            )
             .add_system_to_stage(CoreStage::PostUpdate, Self::update_sprite);
 
         #[cfg(feature = "inspector")]
@@ -98,57 +222,149 @@ impl Plugin {
     fn update_sprite(
         assets: Res<AssetTable>,
         mut cells: Query<
-            (&mut Handle<TextureAtlas>, &mut TextureAtlasSprite, &Cell),
+            (
+                &mut Handle<TextureAtlas>,
+                &mut TextureAtlasSprite,
+                &Cell,
+                &Species,
+            ),
             Changed<Cell>,
         >,
     ) {
-        for (mut handle, mut texture, &cell) in &mut cells {
+        for (mut handle, mut texture, &cell, species) in &mut cells {
             match cell {
                 Cell::Crop { level } => {
                     *handle = assets.crop.clone();
-                    texture.index = (4 - level) as usize;
+                    texture.index = species.sprite_row() * 4 + (4 - level) as usize;
+                    texture.color = Color::WHITE;
                 }
                 Cell::Harvested => {
                     *handle = assets.harvested.clone();
                     texture.index = 0;
+                    texture.color = Color::WHITE;
+                }
+                Cell::Sprout => {
+                    *handle = assets.harvested.clone();
+                    texture.index = 0;
+                    texture.color = Color::hex("a8c070").unwrap();
+                }
+                Cell::Destroyed => {
+                    *handle = assets.harvested.clone();
+                    texture.index = 0;
+                    texture.color = Color::hex("8c6f5a").unwrap();
                 }
+                Cell::Rock | Cell::Pond | Cell::Tree => {
+                    *handle = assets.obstacle.clone();
+                    texture.index = match cell {
+                        Cell::Rock => 0,
+                        Cell::Pond => 1,
+                        _ => 2,
+                    };
+                    texture.color = Color::WHITE;
+                }
+            }
+        }
+    }
+
+    /// Lays out the cells of the level if there is one, generates them from noise otherwise
+    fn layout(settings: &FieldSettings, level: Option<&Level>, rng: &mut GameRng) -> Layout {
+        if let Some(level) = level {
+            match level.layout() {
+                Ok(layout) => return layout,
+                Err(err) => error!("Invalid level, generating a field instead: {err}"),
+            }
+        }
+
+        let mut noise = Fbm::new().set_seed(rng.gen());
+        noise.octaves = 4;
+        let mut obstacle_noise = Fbm::new().set_seed(rng.gen());
+        obstacle_noise.octaves = 2;
+        let mut biome_noise = Fbm::new().set_seed(rng.gen());
+        biome_noise.octaves = 2;
+        let field = Field::new(settings.width, settings.height);
+        let mut cells = Vec::with_capacity((settings.width * settings.height) as usize);
+        for x in 0..settings.width {
+            for y in 0..settings.height {
+                let position = Position(UVec2::new(x, y).as_ivec2());
+                // Leave the combine some room to start
+                let near_start = (*position - *field.start).abs().max_element() <= 2;
+                let obstacle = obstacle_noise.get([x as f64 / 4.0, y as f64 / 4.0]);
+                let cell = if near_start {
+                    Cell::from_noise_value(noise.get([x as f64, y as f64]))
+                } else if obstacle > 0.6 {
+                    Cell::Pond
+                } else if obstacle > 0.45 {
+                    Cell::Tree
+                } else if rng.gen_bool(0.01) {
+                    Cell::Rock
+                } else {
+                    Cell::from_noise_value(noise.get([x as f64, y as f64]))
+                };
+                let species =
+                    Species::from_noise_value(biome_noise.get([x as f64 / 8.0, y as f64 / 8.0]));
+                cells.push((position, cell, species));
             }
         }
+        Layout {
+            width: field.width,
+            height: field.height,
+            cells,
+            start: field.start,
+            direction: field.direction,
+            spawn_edges: field.spawn_edges,
+        }
     }
 
     fn spawn(
         mut commands: Commands,
         mut field: ResMut<Field>,
+        settings: Res<FieldSettings>,
+        level: Option<Res<LevelHandle>>,
+        levels: Res<Assets<Level>>,
         asset_index: Res<AssetTable>,
-        noise: Res<Fbm>,
+        mut rng: ResMut<GameRng>,
     ) {
+        let level = level.and_then(|handle| levels.get(&handle.0));
+        let layout = Self::layout(&settings, level, &mut rng);
+        *field = Field {
+            start: layout.start,
+            direction: layout.direction,
+            spawn_edges: layout.spawn_edges,
+            obstacles: layout
+                .cells
+                .iter()
+                .filter(|(_, c, _)| c.is_obstacle())
+                .count() as u32,
+            ..Field::new(layout.width, layout.height)
+        };
+
         commands
             .spawn_bundle(TransformBundle::default())
             .insert_bundle(VisibilityBundle::default())
             .insert(Name::from("Field"))
             .insert(CellGroup)
             .with_children(|field_commands| {
-                for x in 0..field.width {
-                    for y in 0..field.height {
-                        let cell = Cell::from_noise_value(noise.get([x as f64, y as f64]));
-                        let position = Position(UVec2::new(x, y).as_ivec2());
-                        let entity = field_commands
-                            .spawn_bundle(SpriteSheetBundle {
-                                transform: Transform::from_xyz(x as f32, y as f32, 0.0),
-                                sprite: TextureAtlasSprite {
-                                    custom_size: Some(Vec2::ONE),
-                                    ..Default::default()
-                                },
-                                texture_atlas: asset_index.crop.clone(),
+                for (position, cell, species) in layout.cells {
+                    let entity = field_commands
+                        .spawn_bundle(SpriteSheetBundle {
+                            transform: Transform::from_translation(position.as_vec2().extend(0.0)),
+                            sprite: TextureAtlasSprite {
+                                custom_size: Some(Vec2::ONE),
                                 ..Default::default()
-                            })
-                            .insert(position)
-                            .insert(cell)
-                            .insert(Name::from(format!("Cell ({x},{y})")))
-                            .id();
-
-                        field.map.insert(position, entity);
-                    }
+                            },
+                            texture_atlas: asset_index.crop.clone(),
+                            ..Default::default()
+                        })
+                        .insert(position)
+                        .insert(cell)
+                        .insert(species)
+                        .insert(Collider::Box {
+                            half_size: Vec2::splat(0.5),
+                        })
+                        .insert(Name::from(format!("Cell ({},{})", position.x, position.y)))
+                        .id();
+
+                    field.map.insert(position, entity);
                 }
             });
     }
@@ -156,13 +372,17 @@ impl Plugin {
     fn load_assets(
         mut index: ResMut<AssetTable>,
         asset_server: Res<AssetServer>,
-        mut textures: ResMut<Assets<TextureAtlas>>,
+        textures: Option<ResMut<Assets<TextureAtlas>>>,
     ) {
+        let mut textures = match textures {
+            Some(textures) => textures,
+            None => return,
+        };
         index.crop = textures.add(TextureAtlas::from_grid(
             asset_server.load("sprites/crop.png"),
             Vec2::splat(32.0),
-            2,
-            2,
+            4,
+            Species::ALL.len(),
         ));
         index.harvested = textures.add(TextureAtlas::from_grid(
             asset_server.load("sprites/empty_cell.png"),
@@ -170,5 +390,47 @@ impl Plugin {
             1,
             1,
         ));
+        index.obstacle = textures.add(TextureAtlas::from_grid(
+            asset_server.load("sprites/obstacle.png"),
+            Vec2::splat(32.0),
+            3,
+            1,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_field_size() {
+        let settings = FieldSettings::parse("41x21").unwrap();
+        assert_eq!((settings.width, settings.height), (41, 21));
+        assert!(FieldSettings::parse("41").is_none());
+        assert!(FieldSettings::parse("0x10").is_none());
+        assert!(FieldSettings::parse("ax10").is_none());
+    }
+
+    #[test]
+    fn harvested_cells_grow_back_to_the_max_level() {
+        let mut cell = Cell::Harvested;
+        let mut stages = vec![cell];
+        while cell.can_grow(2) {
+            cell.grow(2);
+            stages.push(cell);
+        }
+        assert_eq!(
+            stages,
+            [
+                Cell::Harvested,
+                Cell::Sprout,
+                Cell::Crop { level: 1 },
+                Cell::Crop { level: 2 }
+            ]
+        );
+        let mut destroyed = Cell::Destroyed;
+        destroyed.grow(4);
+        assert_eq!(destroyed, Cell::Destroyed);
     }
 }
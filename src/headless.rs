@@ -0,0 +1,607 @@
+//! Runs the game without window, renderer nor audio, one simulation step at a time
+//!
+//! Used to test gameplay scenarios.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    asset::AssetPlugin,
+    ecs::event::Events,
+    hierarchy::HierarchyPlugin,
+    input::{keyboard::KeyboardInput, ButtonState, InputPlugin},
+    prelude::*,
+    time::TimePlugin,
+    transform::TransformPlugin,
+    window::{WindowFocused, WindowId},
+};
+use iyes_loopless::prelude::*;
+
+use crate::{
+    combine::{Harvested, Harvester},
+    controls::BindingsFile,
+    enemy::{Enemy, EnemyKilled, Kind, SpawnEnemy},
+    field::{Cell, Field, Position},
+    health::Health,
+    highscore::{HighScoreFile, HighScores},
+    level::{Level, LevelHandle},
+    mouse::Cursor,
+    regrowth::RegrowthSettings,
+    replay::{Playback, Recording, Replay},
+    rng::FixedSeed,
+    simulation::{self, Tick},
+    turret::Ammo,
+    wave::{Director, Phase},
+    GamePlugin, GameState, Score,
+};
+
+pub struct Simulation {
+    app: App,
+    now: Instant,
+}
+
+impl Simulation {
+    /// Creates the game, in the [`GameState::Ready`] state, with the field generated from `seed`
+    pub fn new(seed: u64) -> Self {
+        let mut app = App::new();
+        app.add_plugins_with(MinimalPlugins, |group| group.disable::<TimePlugin>())
+            .insert_resource(Time::default())
+            .add_plugin(AssetPlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .init_resource::<Windows>()
+            .add_event::<ReceivedCharacter>()
+            .add_event::<WindowFocused>()
+            .add_plugin(GamePlugin)
+            .insert_resource(FixedSeed(Some(seed)))
+            .insert_resource(HighScoreFile(None))
+            .insert_resource(BindingsFile(None));
+
+        let now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.update();
+        Self { app, now }
+    }
+
+    /// Creates the game, in the [`GameState::Ready`] state, on `level`
+    pub fn with_level(seed: u64, level: Level) -> Self {
+        let mut sim = Self::new(seed);
+        let layout = level.layout().unwrap();
+        let handle = sim.app.world.resource_mut::<Assets<Level>>().add(level);
+        sim.app.insert_resource(LevelHandle(handle));
+        // Until the game has started over on the level
+        let started = sim.run_until(10, |sim| {
+            let field = sim.app.world.resource::<Field>();
+            (field.width, field.height, field.start) == (layout.width, layout.height, layout.start)
+        });
+        assert!(started, "level not started");
+        sim
+    }
+
+    /// Advances the game by exactly one simulation step
+    pub fn step(&mut self) {
+        self.now += simulation::STEP;
+        self.app
+            .world
+            .resource_mut::<Time>()
+            .update_with_instant(self.now);
+        self.app.update();
+    }
+
+    pub fn run(&mut self, steps: u32) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Runs until `condition` is met, giving up after `max_steps`
+    pub fn run_until(&mut self, max_steps: u32, condition: impl Fn(&Self) -> bool) -> bool {
+        for _ in 0..max_steps {
+            if condition(self) {
+                return true;
+            }
+            self.step();
+        }
+        condition(self)
+    }
+
+    pub fn start(&mut self) {
+        self.app
+            .world
+            .insert_resource(NextState(GameState::Playing));
+        self.step();
+    }
+
+    /// Plays `replay` in place of the input devices, from the next run
+    pub fn play(&mut self, replay: Replay) {
+        self.app.world.insert_resource(Playback::new(replay));
+    }
+
+    /// Replay of the current run, as recorded so far
+    pub fn replay(&self) -> Replay {
+        self.app.world.resource::<Recording>().replay.clone()
+    }
+
+    pub fn enable_regrowth(&mut self) {
+        self.app
+            .world
+            .insert_resource(RegrowthSettings { enabled: true });
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().press(key);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().release(key);
+    }
+
+    /// Presses and releases `key`, over two steps
+    pub fn tap(&mut self, key: KeyCode) {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            self.app.world.send_event(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key),
+                state,
+            });
+            self.step();
+        }
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        for char in text.chars() {
+            self.app.world.send_event(ReceivedCharacter {
+                id: WindowId::primary(),
+                char,
+            });
+        }
+        self.step();
+    }
+
+    pub fn aim(&mut self, target: Vec2) {
+        self.app.world.resource_mut::<Cursor>().0 = target;
+    }
+
+    pub fn hold_fire(&mut self, fire: bool) {
+        let mut mouse = self.app.world.resource_mut::<Input<MouseButton>>();
+        if fire {
+            mouse.press(MouseButton::Left);
+        } else {
+            mouse.release(MouseButton::Left);
+        }
+    }
+
+    pub fn state(&self) -> GameState {
+        self.app.world.resource::<CurrentState<GameState>>().0
+    }
+
+    pub fn tick(&self) -> u64 {
+        **self.app.world.resource::<Tick>()
+    }
+
+    pub fn score(&self) -> f32 {
+        **self.app.world.resource::<Score>()
+    }
+
+    pub fn ammo(&mut self) -> u32 {
+        self.app
+            .world
+            .query::<&Ammo>()
+            .iter(&self.app.world)
+            .map(Ammo::count)
+            .sum()
+    }
+
+    pub fn health(&mut self) -> Option<Health> {
+        self.app
+            .world
+            .query_filtered::<&Health, With<Harvester>>()
+            .iter(&self.app.world)
+            .next()
+            .copied()
+    }
+
+    pub fn high_scores(&self) -> &HighScores {
+        self.app.world.resource::<HighScores>()
+    }
+
+    /// Cell under the combine
+    pub fn combine_cell(&mut self) -> Option<Cell> {
+        let position = *self
+            .app
+            .world
+            .query_filtered::<&Position, With<Harvester>>()
+            .iter(&self.app.world)
+            .next()?;
+        let entity = self.app.world.resource::<Field>().get(position)?;
+        self.app.world.get::<Cell>(entity).copied()
+    }
+
+    pub fn director(&self) -> &Director {
+        self.app.world.resource::<Director>()
+    }
+
+    pub fn enemies(&mut self) -> Vec<Entity> {
+        self.app
+            .world
+            .query_filtered::<Entity, With<Enemy>>()
+            .iter(&self.app.world)
+            .collect()
+    }
+
+    /// Removes every enemy, as if they had all been shot down
+    pub fn clear_enemies(&mut self) {
+        for enemy in self.enemies() {
+            self.app.world.despawn(enemy);
+        }
+    }
+
+    /// Asks for an enemy of the kind named `kind` in the catalogue, spawned during the next step
+    pub fn spawn_enemy(&mut self, kind: &str, position: Vec2) {
+        self.app.world.send_event(SpawnEnemy {
+            kind: kind.to_string(),
+            position,
+        });
+    }
+
+    /// Kills `enemy` as a bullet does, reporting it
+    pub fn kill_enemy(&mut self, enemy: Entity) {
+        let killed = {
+            let entity = self.app.world.entity(enemy);
+            EnemyKilled {
+                position: entity.get::<Transform>().unwrap().translation.truncate(),
+                kind: entity.get::<Kind>().unwrap().clone(),
+            }
+        };
+        self.app.world.despawn(enemy);
+        self.app.world.send_event(killed);
+    }
+
+    pub fn cells(&mut self) -> Vec<Cell> {
+        self.app
+            .world
+            .query::<&Cell>()
+            .iter(&self.app.world)
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_is_full_of_crop_before_start() {
+        let mut sim = Simulation::new(1);
+        let cells = sim.cells();
+        assert_eq!(sim.state(), GameState::Ready);
+        assert!(!cells.is_empty());
+        assert!(cells
+            .iter()
+            .all(|c| matches!(c, Cell::Crop { .. }) || c.is_obstacle()));
+    }
+
+    #[test]
+    fn same_seed_generates_same_field() {
+        let mut first = Simulation::new(42);
+        let mut second = Simulation::new(42);
+        assert_eq!(first.cells(), second.cells());
+    }
+
+    #[test]
+    fn harvesting_gives_score_and_ammo() {
+        let mut sim = Simulation::new(1);
+        sim.start();
+        sim.run(120);
+        assert_eq!(sim.state(), GameState::Playing);
+        assert!(sim.tick() >= 120);
+        assert!(sim.score() > 0.0);
+        assert!(sim.ammo() > 0);
+    }
+
+    #[test]
+    fn steering_changes_the_harvested_cells() {
+        let mut straight = Simulation::new(3);
+        let mut steered = Simulation::new(3);
+        straight.start();
+        steered.start();
+        steered.press(KeyCode::W);
+        straight.run(60);
+        steered.run(60);
+        steered.release(KeyCode::W);
+        straight.run(120);
+        steered.run(120);
+        assert_ne!(straight.cells(), steered.cells());
+    }
+
+    #[test]
+    fn shooting_consumes_ammo() {
+        let mut idle = Simulation::new(5);
+        let mut shooting = Simulation::new(5);
+        idle.start();
+        shooting.start();
+        idle.run(180);
+        shooting.run(180);
+        shooting.aim(Vec2::ZERO);
+        shooting.hold_fire(true);
+        idle.run(30);
+        shooting.run(30);
+        assert!(shooting.ammo() < idle.ammo());
+    }
+
+    #[test]
+    fn enemies_eventually_end_the_run() {
+        let mut sim = Simulation::new(1);
+        sim.start();
+        assert!(sim.run_until(60 * 60 * 5, |sim| sim.state() == GameState::GameOver));
+    }
+
+    #[test]
+    fn first_hit_does_not_end_the_run() {
+        let mut sim = Simulation::new(1);
+        sim.start();
+        let max = sim.health().unwrap().max;
+        let mut steps = 0;
+        while sim.health().unwrap().current == max && steps < 60 * 60 * 5 {
+            sim.step();
+            steps += 1;
+        }
+        assert_eq!(sim.health().unwrap().current, max - 1);
+        assert_eq!(sim.state(), GameState::Playing);
+    }
+
+    #[test]
+    fn game_over_records_the_run_in_the_high_scores() {
+        let mut sim = Simulation::new(1);
+        sim.start();
+        let mut steps = 0;
+        while sim.state() != GameState::GameOver && steps < 60 * 60 * 5 {
+            // Typed while playing, which must not end up in the name
+            sim.app.world.send_event(ReceivedCharacter {
+                id: WindowId::primary(),
+                char: 'w',
+            });
+            sim.step();
+            steps += 1;
+        }
+        assert_eq!(sim.state(), GameState::GameOver);
+        sim.type_text("Farmer");
+        sim.tap(KeyCode::Return);
+        let entries = sim.high_scores().entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "Farmer");
+        assert_eq!(entries[0].seed, 1);
+        assert!(entries[0].duration > Duration::ZERO);
+    }
+
+    #[test]
+    fn high_score_name_can_be_skipped() {
+        let mut sim = Simulation::new(1);
+        sim.start();
+        assert!(sim.run_until(60 * 60 * 5, |sim| sim.state() == GameState::GameOver));
+        sim.tap(KeyCode::Escape);
+        sim.tap(KeyCode::Space);
+        assert!(sim.high_scores().entries().is_empty());
+        assert_eq!(sim.state(), GameState::Ready);
+    }
+
+    #[test]
+    fn pausing_freezes_the_run() {
+        let mut sim = Simulation::new(2);
+        sim.start();
+        sim.run(60);
+        sim.tap(KeyCode::Escape);
+        assert_eq!(sim.state(), GameState::Paused);
+        let (tick, cells) = (sim.tick(), sim.cells());
+        sim.run(300);
+        assert_eq!(sim.tick(), tick);
+        assert_eq!(sim.cells(), cells);
+        sim.tap(KeyCode::Escape);
+        assert_eq!(sim.state(), GameState::Playing);
+        sim.run(60);
+        assert!(sim.tick() > tick);
+    }
+
+    #[test]
+    fn combine_never_drives_onto_obstacles() {
+        for seed in 0..3 {
+            let level = ron::from_str(include_str!("../assets/levels/rocks.level.ron")).unwrap();
+            let mut sim = Simulation::with_level(seed, level);
+            assert!(sim.cells().iter().any(Cell::is_obstacle));
+            sim.start();
+            for step in 0..60 * 30 {
+                if step % 90 == 0 {
+                    sim.release(KeyCode::W);
+                    sim.press(KeyCode::S);
+                } else if step % 90 == 45 {
+                    sim.release(KeyCode::S);
+                    sim.press(KeyCode::W);
+                }
+                sim.step();
+                if sim.state() != GameState::Playing {
+                    break;
+                }
+                assert!(!sim.combine_cell().unwrap().is_obstacle());
+            }
+        }
+    }
+
+    #[test]
+    fn harvested_cells_grow_back_in_regrowth_mode() {
+        let mut sim = Simulation::new(1);
+        sim.enable_regrowth();
+        sim.start();
+        let mut steps = 0;
+        while !sim.cells().contains(&Cell::Sprout) && steps < 60 * 60 {
+            sim.step();
+            steps += 1;
+        }
+        assert!(sim.cells().contains(&Cell::Sprout));
+
+        let mut plain = Simulation::new(1);
+        plain.start();
+        plain.run(steps);
+        assert!(!plain.cells().contains(&Cell::Sprout));
+    }
+
+    #[test]
+    fn crop_never_harvested_does_not_grow_in_regrowth_mode() {
+        let mut sim = Simulation::new(1);
+        sim.enable_regrowth();
+        sim.start();
+        let generated: HashMap<Entity, Cell> = sim
+            .app
+            .world
+            .query::<(Entity, &Cell)>()
+            .iter(&sim.app.world)
+            .map(|(entity, &cell)| (entity, cell))
+            .collect();
+        let mut reader = sim.app.world.resource::<Events<Harvested>>().get_reader();
+        let mut harvested = HashSet::new();
+        for _ in 0..60 * 60 {
+            sim.step();
+            let events = sim.app.world.resource::<Events<Harvested>>();
+            harvested.extend(reader.iter(events).map(|harvested| harvested.entity));
+        }
+        assert!(sim.cells().contains(&Cell::Sprout));
+
+        for (entity, cell) in sim
+            .app
+            .world
+            .query::<(Entity, &Cell)>()
+            .iter(&sim.app.world)
+        {
+            if harvested.contains(&entity) {
+                continue;
+            }
+            // Only trampled by enemies
+            match (generated[&entity], *cell) {
+                (Cell::Crop { level: before }, Cell::Crop { level }) => assert!(level <= before),
+                (before, cell) => assert!(cell == before || cell == Cell::Destroyed),
+            }
+        }
+    }
+
+    #[test]
+    fn each_harvest_is_reported_once_with_its_yield() {
+        let mut sim = Simulation::new(1);
+        sim.start();
+        let combine = sim
+            .app
+            .world
+            .query_filtered::<Entity, With<Harvester>>()
+            .single(&sim.app.world);
+        let mut reader = sim.app.world.resource::<Events<Harvested>>().get_reader();
+        let mut positions = HashSet::new();
+        let mut total = 0.0;
+        for _ in 0..600 {
+            sim.step();
+            let events = sim.app.world.resource::<Events<Harvested>>();
+            let field = sim.app.world.resource::<Field>();
+            for harvested in reader.iter(events) {
+                assert!(positions.insert(harvested.position));
+                assert_eq!(field.get(harvested.position), Some(harvested.entity));
+                assert_eq!(harvested.by, combine);
+                assert!(harvested.level > 0);
+                total += harvested.total_yield();
+            }
+        }
+        assert!(!positions.is_empty());
+        assert_eq!(sim.score(), total);
+    }
+
+    #[test]
+    fn waves_spawn_their_enemies_then_leave_a_calm_intermission() {
+        let mut sim = Simulation::new(1);
+        sim.start();
+        assert!(sim.run_until(60 * 10, |sim| sim.director().wave == 1));
+
+        let mut spawned = HashSet::new();
+        for _ in 0..60 * 60 {
+            if sim.director().phase == Phase::Intermission {
+                break;
+            }
+            sim.step();
+            spawned.extend(sim.enemies());
+            sim.clear_enemies();
+        }
+        assert_eq!(sim.director().wave, 1);
+        assert_eq!(sim.director().phase, Phase::Intermission);
+        assert!(!spawned.is_empty());
+        assert_eq!(spawned.len() as u32, sim.director().spawned);
+
+        let mut calm = 0;
+        while sim.director().wave == 1 && calm < 60 * 60 {
+            sim.step();
+            assert!(sim.enemies().is_empty());
+            calm += 1;
+        }
+        assert_eq!(sim.director().wave, 2);
+        assert!(calm > 60);
+    }
+
+    #[test]
+    fn recorded_run_replays_to_the_same_result() {
+        let mut recorded = Simulation::new(3);
+        recorded.start();
+        recorded.aim(Vec2::new(2.0, 8.0));
+        recorded.hold_fire(true);
+        for step in 0..60 * 10 {
+            if step % 120 == 0 {
+                recorded.release(KeyCode::S);
+                recorded.press(KeyCode::W);
+            } else if step % 120 == 60 {
+                recorded.release(KeyCode::W);
+                recorded.press(KeyCode::S);
+            }
+            recorded.step();
+        }
+        assert!(recorded.score() > 0.0);
+        let replay = recorded.replay();
+
+        let mut replayed = Simulation::new(3);
+        replayed.play(replay);
+        replayed.start();
+        replayed.run(60 * 10);
+
+        assert_eq!(replayed.state(), recorded.state());
+        assert_eq!(replayed.tick(), recorded.tick());
+        assert_eq!(replayed.score(), recorded.score());
+        assert_eq!(replayed.ammo(), recorded.ammo());
+        assert_eq!(replayed.cells(), recorded.cells());
+    }
+
+    #[test]
+    fn wave_is_not_cleared_while_its_last_enemy_splits() {
+        let mut sim = Simulation::new(1);
+        sim.start();
+        assert!(sim.run_until(60 * 60, |sim| sim.director().phase == Phase::Fighting));
+        let others = sim.enemies();
+        assert!(!others.is_empty());
+        sim.spawn_enemy("splitter", Vec2::new(-1.0, 0.0));
+        sim.step();
+        let splitter = sim
+            .enemies()
+            .into_iter()
+            .find(|enemy| !others.contains(enemy))
+            .unwrap();
+        for enemy in sim.enemies() {
+            if enemy != splitter {
+                sim.app.world.despawn(enemy);
+            }
+        }
+
+        sim.kill_enemy(splitter);
+        sim.run(5);
+        assert!(!sim.enemies().is_empty());
+        assert_eq!(sim.director().phase, Phase::Fighting);
+
+        sim.clear_enemies();
+        sim.step();
+        assert_eq!(sim.director().phase, Phase::Intermission);
+    }
+}
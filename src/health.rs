@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{combine::Harvester, enemy::PlayerHit, simulation, GameState};
+
+/// Hit points and invulnerability window of the combine
+///
+/// Can be overridden by inserting the resource before adding the [`Plugin`].
+#[derive(Debug, Clone)]
+pub struct HealthSettings {
+    pub hit_points: u32,
+    pub invulnerability: Duration,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            hit_points: 3,
+            invulnerability: Duration::from_secs_f32(1.5),
+        }
+    }
+}
+
+/// Hit points of the combine or of an enemy
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    pub fn new(hit_points: u32) -> Self {
+        Self {
+            current: hit_points,
+            max: hit_points,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+}
+
+/// Ignores hits until the timer finishes
+#[derive(Debug, Clone, Component, Deref, DerefMut)]
+struct Invulnerable(Timer);
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HealthSettings>()
+            .add_fixed_timestep_system_set(
+                simulation::LABEL,
+                simulation::LATE,
+                ConditionSet::new()
+                    .run_in_state(GameState::Playing)
+                    .with_system(Self::take_damage)
+                    .with_system(Self::recover)
+                    .into(),
+            );
+    }
+}
+
+impl Plugin {
+    fn take_damage(
+        mut commands: Commands,
+        mut hits: EventReader<PlayerHit>,
+        mut combines: Query<(Entity, &mut Health), (With<Harvester>, Without<Invulnerable>)>,
+        settings: Res<HealthSettings>,
+    ) {
+        if hits.iter().count() == 0 {
+            return;
+        }
+        for (entity, mut health) in &mut combines {
+            health.current = health.current.saturating_sub(1);
+            commands
+                .entity(entity)
+                .insert(Invulnerable(Timer::new(settings.invulnerability, false)));
+        }
+    }
+
+    fn recover(
+        mut commands: Commands,
+        mut combines: Query<(Entity, &mut Invulnerable, &mut TextureAtlasSprite)>,
+    ) {
+        for (entity, mut invulnerable, mut sprite) in &mut combines {
+            invulnerable.tick(simulation::STEP);
+            if invulnerable.finished() {
+                sprite.color.set_a(1.0);
+                commands.entity(entity).remove::<Invulnerable>();
+            } else {
+                let blink = (invulnerable.elapsed_secs() * 10.0) as u32 % 2 == 0;
+                sprite.color.set_a(if blink { 0.3 } else { 1.0 });
+            }
+        }
+    }
+}
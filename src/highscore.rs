@@ -0,0 +1,202 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data,
+    enemy::EnemyKilled,
+    rng::Seed,
+    simulation::{self, Tick},
+    GameState, Score,
+};
+
+/// Number of runs kept in the [`HighScores`]
+pub const CAPACITY: usize = 10;
+
+/// Maximum length of a player name
+pub const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub score: f32,
+    pub seed: u64,
+    pub duration: Duration,
+    pub kills: u32,
+    /// Day of the run, as `YYYY-MM-DD`
+    pub date: String,
+}
+
+/// Best runs, highest score first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    entries: Vec<Entry>,
+}
+
+impl HighScores {
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Whether a run with this score would enter the table
+    pub fn qualifies(&self, score: f32) -> bool {
+        self.entries.len() < CAPACITY || self.entries.iter().any(|e| score > e.score)
+    }
+
+    /// Inserts the entry at its rank, returns the rank (0 being the best) if it was kept
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|e| entry.score > e.score)
+            .unwrap_or(self.entries.len());
+        if rank >= CAPACITY {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(CAPACITY);
+        Some(rank)
+    }
+}
+
+/// Where the [`HighScores`] are read at startup and written after each run, `highscores.ron` in the
+/// user data directory by default
+///
+/// `None` forgets the runs when the game exits, as in the headless tests.
+#[derive(Debug, Clone)]
+pub struct HighScoreFile(pub Option<PathBuf>);
+
+/// Statistics of the current run, beyond the [`Score`]
+#[derive(Debug, Clone, Default)]
+pub struct RunStats {
+    pub kills: u32,
+}
+
+impl RunStats {
+    /// Entry for the run that just ended, with an empty name
+    pub fn entry(&self, score: &Score, seed: &Seed, tick: &Tick) -> Entry {
+        Entry {
+            name: String::new(),
+            score: **score,
+            seed: **seed,
+            duration: simulation::STEP * **tick as u32,
+            kills: self.kills,
+            date: today(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScoreFile(data::user_file("highscores.ron")))
+            .init_resource::<HighScores>()
+            .init_resource::<RunStats>()
+            .add_startup_system(Self::load)
+            .add_exit_system(GameState::Ready, Self::reset_stats)
+            .add_fixed_timestep_system(
+                simulation::LABEL,
+                simulation::LATE,
+                Self::count_kills.run_in_state(GameState::Playing),
+            );
+    }
+}
+
+impl Plugin {
+    fn load(mut high_scores: ResMut<HighScores>, file: Res<HighScoreFile>) {
+        let path = match &file.0 {
+            Some(path) if path.exists() => path,
+            _ => return,
+        };
+        match data::load_ron(path) {
+            Ok(loaded) => *high_scores = loaded,
+            Err(err) => error!("Cannot load high scores from {}: {err}", path.display()),
+        }
+    }
+
+    fn reset_stats(mut stats: ResMut<RunStats>) {
+        *stats = RunStats::default();
+    }
+
+    fn count_kills(mut kills: EventReader<EnemyKilled>, mut stats: ResMut<RunStats>) {
+        stats.kills += kills.iter().count() as u32;
+    }
+}
+
+/// Writes the high scores to their file, if any
+pub fn save(high_scores: &HighScores, file: &HighScoreFile) {
+    if let Some(path) = &file.0 {
+        if let Err(err) = data::save_ron(high_scores, path) {
+            error!("Cannot save high scores to {}: {err}", path.display());
+        }
+    }
+}
+
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Gregorian date of a number of days since 1970-01-01
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: f32) -> Entry {
+        Entry {
+            name: String::from("test"),
+            score,
+            seed: 0,
+            duration: Duration::ZERO,
+            kills: 0,
+            date: String::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_best_runs_in_order() {
+        let mut high_scores = HighScores::default();
+        for score in 0..CAPACITY {
+            assert!(high_scores.insert(entry(score as f32)).is_some());
+        }
+        assert!(!high_scores.qualifies(0.0));
+        assert_eq!(high_scores.insert(entry(0.0)), None);
+        assert_eq!(high_scores.insert(entry(4.5)), Some(5));
+        assert_eq!(high_scores.entries().len(), CAPACITY);
+        assert_eq!(high_scores.entries()[0].score, 9.0);
+        assert_eq!(high_scores.entries()[CAPACITY - 1].score, 1.0);
+    }
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_358), (2023, 1, 1));
+    }
+}
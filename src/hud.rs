@@ -1,9 +1,14 @@
-use bevy::prelude::*;
+use std::time::Duration;
+
+use bevy::{prelude::*, ui::FocusPolicy};
 use iyes_loopless::prelude::*;
 
 use crate::{
+    combine::{Harvested, Harvester},
     despawn::despawn,
-    turret::{self, Ammo},
+    health::Health,
+    turret::{self, Ammo, Charge, PowerUp, Round},
+    wave::{WaveCleared, WaveStarted},
     Fonts, GameState, Score,
 };
 
@@ -16,18 +21,70 @@ struct ScoreText;
 #[derive(Component)]
 struct AmmoText;
 
+/// Text naming the round of the next shot
+#[derive(Component)]
+struct RoundText;
+
+#[derive(Component)]
+struct HealthBar;
+
+/// Shows the weapon of the last collected item, hidden once it has run out
+#[derive(Component)]
+struct PowerUpPanel;
+
+/// Strip of all the items of `sprites/item.png`, moved so that only the active one shows
+#[derive(Component)]
+struct PowerUpIcon;
+
+/// Drains as the weapon of the last collected item runs out
+#[derive(Component)]
+struct PowerUpBar;
+
+/// Size of the icon of the active item, in pixels
+const POWER_UP_ICON_SIZE: f32 = 60.0;
+
+/// Announcement over the field, hidden when the timer finishes
+#[derive(Component, Deref, DerefMut)]
+struct Banner(Timer);
+
+#[derive(Component)]
+struct BannerText;
+
+/// Time during which a [`Banner`] is shown
+const BANNER_DURATION: Duration = Duration::from_millis(2500);
+
+/// Yield of a harvested cell, rising over it and fading out until the timer finishes
+#[derive(Component, Deref, DerefMut)]
+struct Popup(Timer);
+
+const POPUP_DURATION: Duration = Duration::from_millis(800);
+
+/// Scale of the popup text, from pixels to cells
+const POPUP_SCALE: f32 = 1.0 / 80.0;
+
+/// Distance risen by a popup, in cells per second
+const POPUP_SPEED: f32 = 0.8;
+
 #[derive(Default)]
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_enter_system(GameState::Playing, Self::spawn)
-            .add_exit_system(GameState::Playing, despawn::<Hud>)
+        app.add_exit_system(GameState::Ready, Self::spawn)
+            .add_enter_system(GameState::Ready, despawn::<Hud>)
+            .add_enter_system(GameState::GameOver, despawn::<Hud>)
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(GameState::Playing)
                     .with_system(Self::update_ammo)
+                    .with_system(Self::update_round)
                     .with_system(Self::update_score)
+                    .with_system(Self::update_health)
+                    .with_system(Self::update_power_up)
+                    .with_system(Self::announce)
+                    .with_system(Self::hide_banner)
+                    .with_system(Self::pop_up)
+                    .with_system(Self::rise)
                     .into(),
             );
     }
@@ -35,6 +92,35 @@ impl bevy::prelude::Plugin for Plugin {
 
 impl Plugin {
     fn spawn(mut commands: Commands, fonts: Res<Fonts>, asset_server: Res<AssetServer>) {
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    display: Display::None,
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
+                focus_policy: FocusPolicy::Pass,
+                ..Default::default()
+            })
+            .insert(Hud)
+            .insert(Banner(Timer::new(BANNER_DURATION, false)))
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: fonts.main.clone(),
+                            font_size: 100.0,
+                            color: Color::BLACK,
+                        },
+                    ))
+                    .insert(BannerText);
+            });
+
         let color = Color::hex("8399b4").unwrap().into();
         commands
             .spawn_bundle(NodeBundle {
@@ -110,6 +196,125 @@ impl Plugin {
                                     },
                                 ));
                             });
+                        parent
+                            .spawn_bundle(
+                                TextBundle::from_section(
+                                    "Empty",
+                                    TextStyle {
+                                        font: fonts.main.clone(),
+                                        font_size: 30.0,
+                                        color: Color::BLACK,
+                                    },
+                                )
+                                .with_style(Style {
+                                    margin: UiRect::new(
+                                        Val::Px(20.0),
+                                        Val::Px(0.0),
+                                        Val::Px(0.0),
+                                        Val::Px(0.0),
+                                    ),
+                                    ..Default::default()
+                                }),
+                            )
+                            .insert(RoundText);
+                    });
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            padding: UiRect::all(Val::Px(10.0)),
+                            display: Display::None,
+                            ..Default::default()
+                        },
+                        color,
+                        ..Default::default()
+                    })
+                    .insert(PowerUpPanel)
+                    .with_children(|parent| {
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(
+                                        Val::Px(POWER_UP_ICON_SIZE),
+                                        Val::Px(POWER_UP_ICON_SIZE),
+                                    ),
+                                    overflow: Overflow::Hidden,
+                                    ..Default::default()
+                                },
+                                color: Color::NONE.into(),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent
+                                    .spawn_bundle(ImageBundle {
+                                        image: UiImage(asset_server.load("sprites/item.png")),
+                                        style: Style {
+                                            position_type: PositionType::Absolute,
+                                            size: Size::new(
+                                                Val::Px(
+                                                    POWER_UP_ICON_SIZE
+                                                        * turret::ITEM_COLUMNS as f32,
+                                                ),
+                                                Val::Px(POWER_UP_ICON_SIZE),
+                                            ),
+                                            ..Default::default()
+                                        },
+                                        ..Default::default()
+                                    })
+                                    .insert(PowerUpIcon);
+                            });
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(120.0), Val::Px(20.0)),
+                                    margin: UiRect::new(
+                                        Val::Px(10.0),
+                                        Val::Px(0.0),
+                                        Val::Px(0.0),
+                                        Val::Px(0.0),
+                                    ),
+                                    ..Default::default()
+                                },
+                                color: Color::hex("505050").unwrap().into(),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent
+                                    .spawn_bundle(NodeBundle {
+                                        style: Style {
+                                            size: Size::new(
+                                                Val::Percent(100.0),
+                                                Val::Percent(100.0),
+                                            ),
+                                            ..Default::default()
+                                        },
+                                        color: Color::hex("e0c040").unwrap().into(),
+                                        ..Default::default()
+                                    })
+                                    .insert(PowerUpBar);
+                            });
+                    });
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(300.0), Val::Px(50.0)),
+                            padding: UiRect::all(Val::Px(10.0)),
+                            ..Default::default()
+                        },
+                        color,
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                    ..Default::default()
+                                },
+                                color: Color::hex("b43c3c").unwrap().into(),
+                                ..Default::default()
+                            })
+                            .insert(HealthBar);
                     });
                 parent
                     .spawn_bundle(NodeBundle {
@@ -130,7 +335,7 @@ impl Plugin {
                         parent
                             .spawn_bundle(
                                 TextBundle::from_section(
-                                    "0%",
+                                    "0",
                                     TextStyle {
                                         font: fonts.main.clone(),
                                         font_size: 40.0,
@@ -167,7 +372,26 @@ impl Plugin {
     ) {
         for ammo in &ammos {
             for mut text in &mut texts {
-                text.sections[0].value = ammo.to_string();
+                text.sections[0].value = ammo.count().to_string();
+            }
+        }
+    }
+
+    fn update_round(
+        ammos: Query<&Ammo, Changed<Ammo>>,
+        mut texts: Query<&mut Text, With<RoundText>>,
+    ) {
+        for ammo in &ammos {
+            for mut text in &mut texts {
+                let section = &mut text.sections[0];
+                let (name, color) = match ammo.loaded() {
+                    Some(Round::Regular) => ("Regular", "5a4a2a"),
+                    Some(Round::Piercing) => ("Piercing", "3f6b2a"),
+                    Some(Round::Heavy) => ("Heavy", "b4561e"),
+                    None => ("Empty", "505050"),
+                };
+                section.value = String::from(name);
+                section.style.color = Color::hex(color).unwrap();
             }
         }
     }
@@ -180,4 +404,122 @@ impl Plugin {
             text.sections[0].value = format!("{}", *score);
         }
     }
+
+    fn announce(
+        mut started: EventReader<WaveStarted>,
+        mut cleared: EventReader<WaveCleared>,
+        mut banners: Query<(&mut Style, &mut Banner)>,
+        mut texts: Query<&mut Text, With<BannerText>>,
+    ) {
+        let cleared = cleared
+            .iter()
+            .last()
+            .map(|c| format!("Wave {} cleared! +{:.0}", c.number, c.bonus));
+        let message = started
+            .iter()
+            .last()
+            .map(|s| format!("Wave {}", s.number))
+            .or(cleared);
+        let message = match message {
+            Some(message) => message,
+            None => return,
+        };
+        for mut text in &mut texts {
+            text.sections[0].value = message.clone();
+        }
+        for (mut style, mut banner) in &mut banners {
+            style.display = Display::Flex;
+            banner.reset();
+        }
+    }
+
+    fn hide_banner(time: Res<Time>, mut banners: Query<(&mut Style, &mut Banner)>) {
+        for (mut style, mut banner) in &mut banners {
+            if banner.tick(time.delta()).just_finished() {
+                style.display = Display::None;
+            }
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn update_power_up(
+        power_ups: Query<&PowerUp, Changed<PowerUp>>,
+        mut panels: Query<&mut Style, With<PowerUpPanel>>,
+        mut icons: Query<&mut Style, (With<PowerUpIcon>, Without<PowerUpPanel>)>,
+        mut bars: Query<
+            &mut Style,
+            (
+                With<PowerUpBar>,
+                Without<PowerUpPanel>,
+                Without<PowerUpIcon>,
+            ),
+        >,
+    ) {
+        for power_up in &power_ups {
+            for mut style in &mut panels {
+                style.display = match power_up.charge {
+                    Charge::Unlimited => Display::None,
+                    _ => Display::Flex,
+                };
+            }
+            for mut style in &mut icons {
+                let offset = power_up.sprite as f32 * POWER_UP_ICON_SIZE;
+                style.position.left = Val::Px(-offset);
+            }
+            for mut style in &mut bars {
+                style.size.width = Val::Percent(100.0 * power_up.charge.fraction());
+            }
+        }
+    }
+
+    /// Shows the yield of each harvested cell over it
+    fn pop_up(mut commands: Commands, fonts: Res<Fonts>, mut harvests: EventReader<Harvested>) {
+        for harvested in harvests.iter() {
+            let translation = harvested.position.as_vec2().extend(5.0);
+            commands
+                .spawn_bundle(Text2dBundle {
+                    text: Text::from_section(
+                        format!("+{}", harvested.total_yield()),
+                        TextStyle {
+                            font: fonts.main.clone(),
+                            font_size: 40.0,
+                            color: Color::hex("5a4a2a").unwrap(),
+                        },
+                    )
+                    .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_translation(translation)
+                        .with_scale(Vec3::splat(POPUP_SCALE)),
+                    ..Default::default()
+                })
+                .insert(Hud)
+                .insert(Popup(Timer::new(POPUP_DURATION, false)));
+        }
+    }
+
+    fn rise(
+        mut commands: Commands,
+        time: Res<Time>,
+        mut popups: Query<(Entity, &mut Transform, &mut Text, &mut Popup)>,
+    ) {
+        for (entity, mut transform, mut text, mut popup) in &mut popups {
+            if popup.tick(time.delta()).finished() {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+            transform.translation.y += POPUP_SPEED * time.delta_seconds();
+            text.sections[0].style.color.set_a(1.0 - popup.percent());
+        }
+    }
+
+    fn update_health(
+        combines: Query<&Health, (With<Harvester>, Changed<Health>)>,
+        mut bars: Query<&mut Style, With<HealthBar>>,
+    ) {
+        for health in &combines {
+            for mut style in &mut bars {
+                let ratio = health.current as f32 / health.max.max(1) as f32;
+                style.size.width = Val::Percent(100.0 * ratio);
+            }
+        }
+    }
 }
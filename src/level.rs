@@ -0,0 +1,244 @@
+use anyhow::{bail, ensure};
+use bevy::{prelude::*, reflect::TypeUuid};
+use iyes_loopless::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    cli,
+    crop::Species,
+    data::RonAssetApp,
+    field::{Cell, Position},
+    GameState,
+};
+
+/// Side of the field from which enemies may come
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Edge {
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+impl Edge {
+    pub const ALL: [Edge; 4] = [Edge::Left, Edge::Right, Edge::Bottom, Edge::Top];
+}
+
+/// Hand-authored field, loaded from `assets/levels/<name>.level.ron` (`--level <name>`)
+///
+/// `rows` draw the field as seen on screen (top row first), one character per cell:
+/// * `1` to `4`: crop of that level
+/// * `.`: harvested cell
+/// * `x`: destroyed cell
+/// * `#`, `~` and `T`: rock, pond and tree, which are obstacles
+///
+/// The optional `species` rows draw the crop species of the cells the same way: `w` for wheat, `c`
+/// for corn and `p` for pumpkins. Cells are wheat without them.
+///
+/// The combine starts on the `start` cell (`(column, row)` from the bottom left corner), heading to
+/// `direction`.
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "0f6c3c4e-5c57-4a4f-9f0e-6b1f7d2a8c31"]
+pub struct Level {
+    rows: Vec<String>,
+    #[serde(default)]
+    species: Vec<String>,
+    start: (u32, u32),
+    direction: (i32, i32),
+    #[serde(default = "all_edges")]
+    spawn_edges: Vec<Edge>,
+}
+
+fn all_edges() -> Vec<Edge> {
+    Edge::ALL.to_vec()
+}
+
+/// Field described by a [`Level`], checked for consistency
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub width: u32,
+    pub height: u32,
+    pub cells: Vec<(Position, Cell, Species)>,
+    pub start: Position,
+    pub direction: IVec2,
+    pub spawn_edges: Vec<Edge>,
+}
+
+impl Level {
+    pub fn layout(&self) -> anyhow::Result<Layout> {
+        let height = self.rows.len() as u32;
+        let width = self.rows.first().map_or(0, |row| row.chars().count()) as u32;
+        ensure!(width > 0 && height > 0, "the level is empty");
+        ensure!(
+            self.species.is_empty() || self.species.len() == self.rows.len(),
+            "there are not as many species rows as rows"
+        );
+
+        let mut cells = Vec::with_capacity((width * height) as usize);
+        for (index, row) in self.rows.iter().enumerate() {
+            let y = height - 1 - index as u32;
+            ensure!(
+                row.chars().count() as u32 == width,
+                "row {index} is not {width} cells long"
+            );
+            let species_row: Vec<char> = match self.species.get(index) {
+                Some(species) => species.chars().collect(),
+                None => vec!['w'; width as usize],
+            };
+            ensure!(
+                species_row.len() as u32 == width,
+                "species row {index} is not {width} cells long"
+            );
+            for ((x, c), s) in row.chars().enumerate().zip(species_row) {
+                let cell = match c {
+                    '1'..='4' => Cell::Crop {
+                        level: c as u8 - b'0',
+                    },
+                    '.' => Cell::Harvested,
+                    'x' => Cell::Destroyed,
+                    '#' => Cell::Rock,
+                    '~' => Cell::Pond,
+                    'T' => Cell::Tree,
+                    _ => bail!("unknown cell '{c}' in row {index}"),
+                };
+                let species = match Species::from_char(s) {
+                    Some(species) => species,
+                    None => bail!("unknown species '{s}' in species row {index}"),
+                };
+                cells.push((Position(UVec2::new(x as u32, y).as_ivec2()), cell, species));
+            }
+        }
+
+        let (x, y) = self.start;
+        ensure!(x < width && y < height, "the start is out of the field");
+        let start = Position(UVec2::new(x, y).as_ivec2());
+        ensure!(
+            cells
+                .iter()
+                .all(|(p, c, _)| *p != start || !c.is_obstacle()),
+            "the start is on an obstacle"
+        );
+        let direction = IVec2::from(self.direction);
+        ensure!(
+            direction.x.abs() + direction.y.abs() == 1,
+            "the direction must be one of (1, 0), (-1, 0), (0, 1) or (0, -1)"
+        );
+        ensure!(!self.spawn_edges.is_empty(), "there is no spawn edge");
+
+        Ok(Layout {
+            width,
+            height,
+            cells,
+            start,
+            direction,
+            spawn_edges: self.spawn_edges.clone(),
+        })
+    }
+}
+
+/// Name of the level the runs are played on (`--level <name>`), instead of a generated field
+#[derive(Debug, Clone)]
+pub struct LevelName(pub String);
+
+#[derive(Debug, Clone)]
+pub struct LevelHandle(pub Handle<Level>);
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        if let Some(name) = cli::arg("level") {
+            app.insert_resource(LevelName(name));
+        }
+        app.add_ron_asset::<Level>(&["level.ron"])
+            .add_startup_system(Self::load)
+            .add_system(Self::reload);
+    }
+}
+
+impl Plugin {
+    fn load(mut commands: Commands, server: Res<AssetServer>, name: Option<Res<LevelName>>) {
+        if let Some(name) = name {
+            let handle = server.load(format!("levels/{}.level.ron", name.0).as_str());
+            commands.insert_resource(LevelHandle(handle));
+        }
+    }
+
+    /// Starts over once the level is loaded, and whenever it changes (with the `dev` feature)
+    fn reload(
+        mut commands: Commands,
+        mut events: EventReader<AssetEvent<Level>>,
+        level: Option<Res<LevelHandle>>,
+    ) {
+        let level = match level {
+            Some(level) => level,
+            None => return,
+        };
+        let changed = events.iter().any(|event| match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => *handle == level.0,
+            AssetEvent::Removed { .. } => false,
+        });
+        if changed {
+            commands.insert_resource(NextState(GameState::Ready));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_layout_from_the_rows() {
+        let level: Level = ron::from_str(
+            r#"(
+                rows: [
+                    "44.",
+                    "x21",
+                ],
+                species: [
+                    "wcp",
+                    "www",
+                ],
+                start: (1, 0),
+                direction: (0, 1),
+                spawn_edges: [Top],
+            )"#,
+        )
+        .unwrap();
+        let layout = level.layout().unwrap();
+        assert_eq!((layout.width, layout.height), (3, 2));
+        assert_eq!(layout.start, Position(IVec2::new(1, 0)));
+        assert_eq!(layout.direction, IVec2::Y);
+        assert_eq!(layout.spawn_edges, vec![Edge::Top]);
+        let cell_at = |x, y| {
+            layout
+                .cells
+                .iter()
+                .find(|(p, ..)| *p == Position(IVec2::new(x, y)))
+                .map(|&(_, c, s)| (c, s))
+        };
+        assert_eq!(
+            cell_at(0, 1),
+            Some((Cell::Crop { level: 4 }, Species::Wheat))
+        );
+        assert_eq!(
+            cell_at(1, 1),
+            Some((Cell::Crop { level: 4 }, Species::Corn))
+        );
+        assert_eq!(cell_at(2, 1), Some((Cell::Harvested, Species::Pumpkin)));
+        assert_eq!(cell_at(0, 0), Some((Cell::Destroyed, Species::Wheat)));
+        assert_eq!(
+            cell_at(2, 0),
+            Some((Cell::Crop { level: 1 }, Species::Wheat))
+        );
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        let level: Level =
+            ron::from_str(r#"(rows: ["444", "44"], start: (0, 0), direction: (1, 0))"#).unwrap();
+        assert!(level.layout().is_err());
+    }
+}
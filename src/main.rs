@@ -2,27 +2,48 @@ use std::fmt::Display;
 
 use bevy::{prelude::*, render::texture::ImageSettings};
 use bevy_kira_audio::prelude::*;
-use combine::Harvested;
-use enemy::PlayerHit;
-use field::{Cell, Field};
+use combine::{Harvested, Harvester};
+use enemy::EnemyKilled;
+use health::Health;
 use iyes_loopless::prelude::*;
+use wave::WaveCleared;
 
+mod audio;
 mod barrier;
 mod camera;
+mod cli;
+mod collision;
 mod combine;
+mod controls;
+mod crop;
+mod data;
 mod despawn;
+mod difficulty;
 mod enemy;
 mod field;
+#[cfg(test)]
+mod headless;
+mod health;
+mod highscore;
 mod hud;
+mod level;
 mod mouse;
 mod movement;
+mod regrowth;
+mod replay;
+mod rng;
 mod screens;
+mod simulation;
+mod spatial;
+mod steering;
 mod turret;
+mod wave;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 enum GameState {
     Ready,
     Playing,
+    Paused,
     GameOver,
 }
 
@@ -31,17 +52,26 @@ struct Fonts {
     main: Handle<Font>,
 }
 
+/// Yield of the crop harvested during the run (see [`Harvested::total_yield`]), plus the score of
+/// the enemies killed and the bonus of the waves cleared
 #[derive(Default, Deref, DerefMut)]
 struct Score(f32);
 
 impl Display for Score {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:.0}%", self.0)
+        write!(f, "{:.0}", self.0)
     }
 }
 
 fn main() {
     let mut app = App::new();
+    // Lets designers iterate on the data files (like the levels) while the game runs
+    #[cfg(feature = "dev")]
+    app.insert_resource(bevy::asset::AssetServerSettings {
+        watch_for_changes: true,
+        ..Default::default()
+    });
+
     app.insert_resource(ImageSettings::default_nearest())
         .insert_resource(ClearColor(Color::hex("5a655a").unwrap()))
         .insert_resource(WindowDescriptor {
@@ -55,29 +85,64 @@ fn main() {
     #[cfg(feature = "inspector")]
     app.add_plugin(bevy_inspector_egui::WorldInspectorPlugin::default());
 
-    app.add_loopless_state(GameState::Ready)
-        .init_resource::<Fonts>()
-        .init_resource::<Score>()
-        .add_startup_system(load_fonts)
-        .add_plugin(camera::Plugin::default())
-        .add_plugin(mouse::Plugin::default())
-        .add_plugin(field::Plugin::default())
-        .add_plugin(combine::Plugin::default())
-        .add_plugin(enemy::Plugin::default())
-        .add_plugin(turret::Plugin::default())
-        .add_plugin(barrier::Plugin::default())
-        .add_plugin(hud::Plugin::default())
-        .add_plugins(screens::Plugins::default())
-        .add_system_set(movement::systems())
-        .add_system_set(despawn::systems())
-        .add_enter_system(GameState::Ready, reset_score)
-        .add_system(update_score.run_in_state(GameState::Playing))
-        .add_system(game_over.run_in_state(GameState::Playing))
+    app.add_plugin(GamePlugin)
+        .add_plugin(audio::Plugin::default())
         .run();
 }
 
-fn game_over(mut commands: Commands, mut player_hits: EventReader<PlayerHit>) {
-    if player_hits.iter().count() > 0 {
+/// The whole game, independently of the window, renderer and audio output
+///
+/// It expects the asset, input and time resources to be available, but degrades gracefully when the
+/// rendering assets (textures, texture atlases, fonts) cannot be loaded.
+struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_loopless_state(GameState::Ready)
+            .add_plugin(simulation::Plugin::default())
+            .init_resource::<Fonts>()
+            .init_resource::<Score>()
+            .add_startup_system(load_fonts)
+            .add_plugin(rng::Plugin::default())
+            .add_plugin(camera::Plugin::default())
+            .add_plugin(mouse::Plugin::default())
+            .add_plugin(controls::Plugin::default())
+            .add_plugin(level::Plugin::default())
+            .add_plugin(field::Plugin::default())
+            .add_plugin(regrowth::Plugin::default())
+            .add_plugin(combine::Plugin::default())
+            .add_plugin(difficulty::Plugin::default())
+            .add_plugin(enemy::Plugin::default())
+            .add_plugin(wave::Plugin::default())
+            .add_plugin(health::Plugin::default())
+            .add_plugin(turret::Plugin::default())
+            .add_plugin(barrier::Plugin::default())
+            .add_plugin(hud::Plugin::default())
+            .add_plugin(replay::Plugin::default())
+            .add_plugin(highscore::Plugin::default())
+            .add_plugins(screens::Plugins::default())
+            .add_fixed_timestep_system_set(
+                simulation::LABEL,
+                simulation::UPDATE,
+                movement::systems(),
+            )
+            .add_fixed_timestep_system_set(
+                simulation::LABEL,
+                simulation::UPDATE,
+                despawn::systems(),
+            )
+            .add_enter_system(GameState::Ready, reset_score)
+            .add_system(update_score.run_in_state(GameState::Playing))
+            .add_fixed_timestep_system(
+                simulation::LABEL,
+                simulation::LATE,
+                game_over.run_in_state(GameState::Playing),
+            );
+    }
+}
+
+fn game_over(mut commands: Commands, combines: Query<&Health, With<Harvester>>) {
+    if combines.iter().any(Health::is_dead) {
         commands.insert_resource(NextState(GameState::GameOver));
     }
 }
@@ -90,18 +155,17 @@ fn reset_score(mut score: ResMut<Score>) {
     **score = 0.0;
 }
 
+/// Counts every harvest, so that cells growing back count again
 fn update_score(
     mut harvested: EventReader<Harvested>,
+    mut kills: EventReader<EnemyKilled>,
+    mut waves: EventReader<WaveCleared>,
     mut score: ResMut<Score>,
-    cells: Query<&Cell>,
-    field: Res<Field>,
 ) {
-    if harvested.iter().count() == 0 {
-        return;
+    let points: f32 = harvested.iter().map(Harvested::total_yield).sum::<f32>()
+        + kills.iter().map(|killed| killed.kind.score).sum::<f32>()
+        + waves.iter().map(|cleared| cleared.bonus).sum::<f32>();
+    if points > 0.0 {
+        **score += points;
     }
-    let count = cells
-        .iter()
-        .filter(|c| matches!(c, Cell::Harvested))
-        .count();
-    **score = 100.0 * count as f32 / (field.width * field.height) as f32;
 }
@@ -4,7 +4,7 @@ use iyes_loopless::prelude::IntoConditionalSystem;
 use crate::GameState;
 
 #[derive(Debug, Clone, Default, Deref)]
-pub struct Cursor(Vec2);
+pub struct Cursor(pub(crate) Vec2);
 
 #[derive(Default)]
 pub struct Plugin;
@@ -30,9 +30,13 @@ impl Plugin {
         };
 
         let window = if let RenderTarget::Window(id) = camera.target {
-            windows.get(id).unwrap()
+            windows.get(id)
         } else {
-            windows.get_primary().unwrap()
+            windows.get_primary()
+        };
+        let window = match window {
+            Some(window) => window,
+            None => return,
         };
 
         if let Some(screen_pos) = window.cursor_position() {
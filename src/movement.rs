@@ -1,14 +1,41 @@
 use bevy::prelude::*;
+use itertools_num::linspace;
+use iyes_loopless::prelude::*;
+
+use crate::{simulation::STEP, GameState};
 
 #[derive(Debug, Clone, Copy, Component, Default, Deref, DerefMut)]
 pub struct Velocity(pub(crate) Vec2);
 
 pub fn systems() -> SystemSet {
-    SystemSet::new().with_system(movement)
+    ConditionSet::new()
+        .run_not_in_state(GameState::Paused)
+        .with_system(movement)
+        .into()
+}
+
+/// Angles of `count` shots fanned out evenly over `spread` radians around `center`, a single shot
+/// going straight at `center`
+pub fn fan(center: f32, spread: f32, count: usize) -> impl Iterator<Item = f32> {
+    let half_spread = if count > 1 { spread / 2.0 } else { 0.0 };
+    linspace(center - half_spread, center + half_spread, count)
 }
 
-fn movement(time: Res<Time>, mut movings: Query<(&mut Transform, &Velocity)>) {
+fn movement(mut movings: Query<(&mut Transform, &Velocity)>) {
     for (mut transform, &velocity) in &mut movings {
-        transform.translation += velocity.extend(0.0) * time.delta_seconds();
+        transform.translation += velocity.extend(0.0) * STEP.as_secs_f32();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fans_out_around_the_center() {
+        assert_eq!(fan(1.0, 0.4, 1).collect::<Vec<_>>(), [1.0]);
+        let angles: Vec<f32> = fan(1.0, 0.4, 3).collect();
+        assert_eq!(angles.len(), 3);
+        assert!((angles[0] - 0.8).abs() < 1e-6 && (angles[2] - 1.2).abs() < 1e-6);
     }
 }
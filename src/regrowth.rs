@@ -0,0 +1,130 @@
+use bevy::{prelude::*, utils::HashSet};
+use iyes_loopless::prelude::*;
+
+use crate::{
+    cli,
+    combine::Harvested,
+    difficulty::Difficulty,
+    field::{Cell, Position},
+    simulation, GameState,
+};
+
+/// Crop level up to which the cells grow back
+const MAX_LEVEL: u8 = 4;
+
+/// Growth stages climbed per second by a cell without crop around it
+const BASE_RATE: f32 = 0.05;
+
+/// Additional rate, relative to [`BASE_RATE`], for each neighbouring cell holding crop
+const NEIGHBOUR_BOOST: f32 = 0.25;
+
+/// Part of the rate lost at the highest difficulty intensity
+const DIFFICULTY_SLOWDOWN: f32 = 0.6;
+
+const NEIGHBOURS: [IVec2; 8] = [
+    IVec2::new(-1, -1),
+    IVec2::new(0, -1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 0),
+    IVec2::new(1, 0),
+    IVec2::new(-1, 1),
+    IVec2::new(0, 1),
+    IVec2::new(1, 1),
+];
+
+/// Whether harvested cells grow back over time during the next runs (`--regrowth`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegrowthSettings {
+    pub enabled: bool,
+}
+
+/// Progress of a harvested cell toward its next growth stage, the crop never harvested keeping its
+/// level
+#[derive(Debug, Clone, Copy, Component)]
+struct Growth {
+    /// Stage the progress applies to, so that it starts over when the cell is harvested or
+    /// trampled
+    stage: Cell,
+    /// From 0 to 1
+    progress: f32,
+}
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RegrowthSettings {
+            enabled: cli::flag("regrowth"),
+        })
+        .add_fixed_timestep_system(
+            simulation::LABEL,
+            simulation::UPDATE,
+            Self::grow
+                .run_in_state(GameState::Playing)
+                .run_if(Self::enabled),
+        )
+        // After the harvests
+        .add_fixed_timestep_system(
+            simulation::LABEL,
+            simulation::LATE,
+            Self::plant
+                .run_in_state(GameState::Playing)
+                .run_if(Self::enabled),
+        );
+    }
+}
+
+impl Plugin {
+    fn enabled(settings: Res<RegrowthSettings>) -> bool {
+        settings.enabled
+    }
+
+    /// Starts growing the harvested cells back
+    fn plant(mut commands: Commands, mut harvests: EventReader<Harvested>) {
+        for harvested in harvests.iter() {
+            commands.entity(harvested.entity).insert(Growth {
+                stage: Cell::Harvested,
+                progress: 0.0,
+            });
+        }
+    }
+
+    /// Grows the harvested cells, faster next to crop and slower as the difficulty rises
+    fn grow(
+        difficulty: Res<Difficulty>,
+        mut cells: Query<(&mut Cell, &Position, Option<&mut Growth>)>,
+    ) {
+        let crop: HashSet<Position> = cells
+            .iter()
+            .filter(|(cell, ..)| matches!(cell, Cell::Crop { .. }))
+            .map(|(_, &position, _)| position)
+            .collect();
+        let slowdown = 1.0 - DIFFICULTY_SLOWDOWN * difficulty.intensity;
+
+        for (mut cell, position, growth) in &mut cells {
+            let mut growth = match growth {
+                Some(growth) if cell.can_grow(MAX_LEVEL) => growth,
+                _ => continue,
+            };
+            let neighbours = NEIGHBOURS
+                .iter()
+                .filter(|&&offset| crop.contains(&Position(**position + offset)))
+                .count();
+            let rate = BASE_RATE * (1.0 + NEIGHBOUR_BOOST * neighbours as f32) * slowdown;
+            if growth.stage != *cell {
+                // Harvested or trampled since
+                growth.stage = *cell;
+                growth.progress = 0.0;
+            }
+            growth.progress += rate * simulation::STEP.as_secs_f32();
+            if growth.progress >= 1.0 {
+                cell.grow(MAX_LEVEL);
+                *growth = Growth {
+                    stage: *cell,
+                    progress: 0.0,
+                };
+            }
+        }
+    }
+}
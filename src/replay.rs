@@ -0,0 +1,190 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli,
+    controls::Controls,
+    data,
+    field::FieldSettings,
+    level::LevelName,
+    regrowth::RegrowthSettings,
+    rng::{FixedSeed, Seed},
+    simulation::{self, Tick},
+    turret::Shot,
+    GameState,
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum PlayerInput {
+    Steer(Option<[i32; 2]>),
+    Fire { aim: [f32; 2] },
+}
+
+/// Seed and player inputs of a run, enough to play it again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    seed: u64,
+    /// Width and height of the field, if not the default one
+    #[serde(default)]
+    field: Option<[u32; 2]>,
+    /// Name of the level, if played on one
+    #[serde(default)]
+    level: Option<String>,
+    /// Whether harvested cells grew back
+    #[serde(default)]
+    regrowth: bool,
+    inputs: Vec<(u64, PlayerInput)>,
+}
+
+/// Replay fed to the [`Controls`] in place of the input devices (`--replay <file>`)
+#[derive(Debug, Clone, Default)]
+pub struct Playback {
+    replay: Replay,
+    next: usize,
+    steer: Option<IVec2>,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    pub replay: Replay,
+    steer: Option<IVec2>,
+}
+
+/// File in which the replay of each run is written (`--record <file>`)
+struct RecordPath(PathBuf);
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recording>()
+            .add_exit_system(GameState::Ready, Self::start)
+            .add_enter_system(
+                GameState::GameOver,
+                Self::save.run_if_resource_exists::<RecordPath>(),
+            )
+            .add_fixed_timestep_system(
+                simulation::LABEL,
+                simulation::INPUT,
+                Self::play
+                    .run_in_state(GameState::Playing)
+                    .run_if_resource_exists::<Playback>()
+                    .after(simulation::Advance),
+            )
+            .add_fixed_timestep_system(
+                simulation::LABEL,
+                simulation::LATE,
+                Self::record.run_in_state(GameState::Playing),
+            );
+
+        // Must be added after `rng::Plugin`, `field::Plugin`, `level::Plugin` and `regrowth::Plugin`,
+        // to take precedence over `--seed`, `--field`, `--level` and `--regrowth`
+        if let Some(path) = cli::arg("replay") {
+            match data::load_ron::<Replay>(&path) {
+                Ok(replay) => {
+                    if let Some([width, height]) = replay.field {
+                        app.insert_resource(FieldSettings { width, height });
+                    }
+                    if let Some(level) = &replay.level {
+                        app.insert_resource(LevelName(level.clone()));
+                    }
+                    app.insert_resource(RegrowthSettings {
+                        enabled: replay.regrowth,
+                    })
+                    .insert_resource(FixedSeed(Some(replay.seed)))
+                    .insert_resource(Playback::new(replay));
+                }
+                Err(err) => error!("Cannot load replay from {path}: {err}"),
+            }
+        }
+        if let Some(path) = cli::arg("record") {
+            app.insert_resource(RecordPath(path.into()));
+        }
+    }
+}
+
+impl Plugin {
+    fn start(
+        mut recording: ResMut<Recording>,
+        playback: Option<ResMut<Playback>>,
+        seed: Res<Seed>,
+        field: Res<FieldSettings>,
+        level: Option<Res<LevelName>>,
+        regrowth: Res<RegrowthSettings>,
+    ) {
+        let default_field = FieldSettings::default();
+        let field = (field.width != default_field.width || field.height != default_field.height)
+            .then_some([field.width, field.height]);
+        *recording = Recording {
+            replay: Replay {
+                seed: **seed,
+                field,
+                level: level.map(|level| level.0.clone()),
+                regrowth: regrowth.enabled,
+                inputs: Vec::new(),
+            },
+            steer: None,
+        };
+        if let Some(mut playback) = playback {
+            playback.next = 0;
+            playback.steer = None;
+        }
+    }
+
+    fn play(mut controls: ResMut<Controls>, mut playback: ResMut<Playback>, tick: Res<Tick>) {
+        controls.fire = false;
+        while let Some(&(at, input)) = playback.replay.inputs.get(playback.next) {
+            if at > **tick {
+                break;
+            }
+            playback.next += 1;
+            match input {
+                PlayerInput::Steer(steer) => playback.steer = steer.map(IVec2::from),
+                PlayerInput::Fire { aim } => {
+                    controls.fire = true;
+                    controls.aim = Vec2::from(aim);
+                }
+            }
+        }
+        controls.steer = playback.steer;
+    }
+
+    fn record(
+        controls: Res<Controls>,
+        mut shots: EventReader<Shot>,
+        mut recording: ResMut<Recording>,
+        tick: Res<Tick>,
+    ) {
+        if controls.steer != recording.steer {
+            recording.steer = controls.steer;
+            let input = PlayerInput::Steer(controls.steer.map(|d| d.to_array()));
+            recording.replay.inputs.push((**tick, input));
+        }
+        if shots.iter().count() > 0 {
+            let input = PlayerInput::Fire {
+                aim: controls.aim.to_array(),
+            };
+            recording.replay.inputs.push((**tick, input));
+        }
+    }
+
+    fn save(recording: Res<Recording>, path: Res<RecordPath>) {
+        match data::save_ron(&recording.replay, &path.0) {
+            Ok(()) => info!("Replay saved to {}", path.0.display()),
+            Err(err) => error!("Cannot save replay to {}: {err}", path.0.display()),
+        }
+    }
+}
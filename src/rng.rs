@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::{cli, GameState};
+
+/// Seed of the current run
+#[derive(Debug, Clone, Copy, Default, Deref)]
+pub struct Seed(u64);
+
+/// Seed given with `--seed` on the command line, used for every run instead of a random one
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedSeed(pub Option<u64>);
+
+/// Source of all randomness of a run, so that it can be replayed from its [`Seed`]
+#[derive(Debug, Clone, Deref, DerefMut)]
+pub struct GameRng(StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(StdRng::seed_from_u64(0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, SystemLabel)]
+pub struct Reseed;
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FixedSeed(cli::arg("seed").and_then(|s| s.parse().ok())))
+            .init_resource::<Seed>()
+            .init_resource::<GameRng>()
+            .add_enter_system(GameState::Ready, Self::reseed.label(Reseed));
+    }
+}
+
+impl Plugin {
+    fn reseed(mut seed: ResMut<Seed>, mut rng: ResMut<GameRng>, fixed: Res<FixedSeed>) {
+        seed.0 = fixed.0.unwrap_or_else(|| thread_rng().gen());
+        rng.0 = StdRng::seed_from_u64(seed.0);
+    }
+}
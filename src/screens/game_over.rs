@@ -1,21 +1,47 @@
 use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 
-use crate::{despawn::despawn, Fonts, GameState, Score};
+use crate::{
+    despawn::despawn,
+    field::{Cell, Field},
+    highscore::{self, Entry, HighScoreFile, HighScores, RunStats, MAX_NAME_LENGTH},
+    regrowth::RegrowthSettings,
+    rng::Seed,
+    simulation::Tick,
+    Fonts, GameState, Score,
+};
 
 use super::spawn_screen;
 
 #[derive(Debug, Clone, Copy, Default, Component)]
 struct GameOverScreen;
 
+/// Text showing the name being typed, then the rank of the run
+#[derive(Debug, Clone, Copy, Component)]
+struct NameText;
+
+/// Run entering the high scores, waiting for the player to type their name
+#[derive(Debug, Clone)]
+pub struct NameEntry(Entry);
+
 #[derive(Default)]
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_enter_system(GameState::GameOver, Self::spawn)
+            .add_enter_system(GameState::GameOver, Self::forget_typing)
             .add_exit_system(GameState::GameOver, despawn::<GameOverScreen>)
-            .add_system(Self::restart.run_in_state(GameState::GameOver));
+            .add_system(
+                Self::restart
+                    .run_in_state(GameState::GameOver)
+                    .run_unless_resource_exists::<NameEntry>(),
+            )
+            .add_system(
+                Self::enter_name
+                    .run_in_state(GameState::GameOver)
+                    .run_if_resource_exists::<NameEntry>(),
+            );
     }
 }
 
@@ -26,7 +52,77 @@ impl Plugin {
         }
     }
 
-    fn spawn(mut commands: Commands, fonts: Res<Fonts>, score: Res<Score>) {
+    /// Drops the characters typed while playing, so that they do not end up in the name
+    fn forget_typing(mut characters: ResMut<Events<ReceivedCharacter>>) {
+        characters.clear();
+    }
+
+    fn enter_name(
+        mut commands: Commands,
+        mut characters: EventReader<ReceivedCharacter>,
+        inputs: Res<Input<KeyCode>>,
+        mut entry: ResMut<NameEntry>,
+        mut high_scores: ResMut<HighScores>,
+        file: Res<HighScoreFile>,
+        mut texts: Query<&mut Text, With<NameText>>,
+    ) {
+        let name = &mut entry.0.name;
+        for character in characters.iter() {
+            let c = character.char;
+            if (c.is_alphanumeric() || c == '-' || c == '_')
+                && name.chars().count() < MAX_NAME_LENGTH
+            {
+                name.push(c);
+            }
+        }
+        if inputs.just_pressed(KeyCode::Back) {
+            name.pop();
+        }
+
+        let skipped = inputs.just_pressed(KeyCode::Escape)
+            || (inputs.just_pressed(KeyCode::Return) && name.is_empty());
+        let text = if skipped {
+            // Leaving the run out of the high scores
+            commands.remove_resource::<NameEntry>();
+            String::new()
+        } else if inputs.just_pressed(KeyCode::Return) {
+            commands.remove_resource::<NameEntry>();
+            let rank = high_scores.insert(entry.0.clone());
+            highscore::save(&high_scores, &file);
+            match rank {
+                Some(rank) => format!("High score #{}!", rank + 1),
+                None => String::new(),
+            }
+        } else {
+            format!("Your name: {name}_")
+        };
+        for mut name_text in &mut texts {
+            name_text.sections[0].value = text.clone();
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn spawn(
+        mut commands: Commands,
+        fonts: Res<Fonts>,
+        score: Res<Score>,
+        field: Res<Field>,
+        cells: Query<&Cell>,
+        seed: Res<Seed>,
+        tick: Res<Tick>,
+        stats: Res<RunStats>,
+        high_scores: Res<HighScores>,
+        regrowth: Res<RegrowthSettings>,
+    ) {
+        let new_high_score = high_scores.qualifies(**score);
+        if new_high_score {
+            commands.insert_resource(NameEntry(stats.entry(&score, &seed, &tick)));
+        }
+        let destroyed = cells
+            .iter()
+            .filter(|c| matches!(c, Cell::Destroyed))
+            .count();
+        let lost = 100.0 * destroyed as f32 / field.arable() as f32;
         spawn_screen::<GameOverScreen>(&mut commands, |parent| {
             parent.spawn_bundle(
                 TextBundle::from_section(
@@ -49,7 +145,7 @@ impl Plugin {
             };
             parent.spawn_bundle(
                 TextBundle::from_sections([
-                    TextSection::new("You harvested ", text_style.clone()),
+                    TextSection::new("You scored ", text_style.clone()),
                     TextSection::new(
                         format!("{}", *score),
                         TextStyle {
@@ -57,7 +153,7 @@ impl Plugin {
                             ..text_style.clone()
                         },
                     ),
-                    TextSection::new(" of the field!", text_style),
+                    TextSection::new(" points!", text_style.clone()),
                 ])
                 .with_style(Style {
                     margin: UiRect::all(Val::Px(10.0)),
@@ -66,7 +162,71 @@ impl Plugin {
             );
             parent.spawn_bundle(
                 TextBundle::from_section(
-                    "Press <space> to restart",
+                    format!("The aliens destroyed {lost:.0}% of the field"),
+                    TextStyle {
+                        font_size: 40.0,
+                        ..text_style.clone()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..Default::default()
+                }),
+            );
+            if regrowth.enabled {
+                parent.spawn_bundle(
+                    TextBundle::from_section(
+                        "Regrowth mode: harvested cells grew back and were harvested again",
+                        TextStyle {
+                            font_size: 30.0,
+                            ..text_style
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(10.0)),
+                        ..Default::default()
+                    }),
+                );
+            }
+            parent.spawn_bundle(
+                TextBundle::from_section(
+                    format!("Seed: {}", **seed),
+                    TextStyle {
+                        font: fonts.main.clone(),
+                        color: Color::DARK_GRAY,
+                        font_size: 30.0,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..Default::default()
+                }),
+            );
+            if new_high_score {
+                parent
+                    .spawn_bundle(
+                        TextBundle::from_section(
+                            "Your name: _",
+                            TextStyle {
+                                font: fonts.main.clone(),
+                                color: Color::BLACK,
+                                font_size: 40.0,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(10.0)),
+                            ..Default::default()
+                        }),
+                    )
+                    .insert(NameText);
+            }
+            parent.spawn_bundle(
+                TextBundle::from_section(
+                    if new_high_score {
+                        "Type your name and <enter>, <escape> to skip, then <space> to restart"
+                    } else {
+                        "Press <space> to restart"
+                    },
                     TextStyle {
                         font: fonts.main.clone(),
                         color: Color::BLACK,
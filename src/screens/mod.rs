@@ -1,7 +1,12 @@
 use bevy::prelude::*;
 
-mod game_over;
+pub mod game_over;
+mod pause;
 mod ready;
+mod settings;
+
+const BUTTON_COLOR: &str = "5a655a";
+const HOVERED_BUTTON_COLOR: &str = "75836f";
 
 #[derive(Default)]
 pub struct Plugins;
@@ -10,6 +15,9 @@ impl PluginGroup for Plugins {
     fn build(&mut self, group: &mut bevy::app::PluginGroupBuilder) {
         group
             .add(game_over::Plugin::default())
+            .add(pause::Plugin::default())
+            .add(settings::Plugin::default())
+            .add(ButtonPlugin)
             .add(ready::Plugin::default());
     }
 }
@@ -46,3 +54,55 @@ fn spawn_screen<C: Component + Default>(
                 .with_children(children);
         });
 }
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    label: &str,
+    action: impl Component,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(300.0), Val::Px(70.0)),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::hex(BUTTON_COLOR).unwrap().into(),
+            ..Default::default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 40.0,
+                    color: Color::BLACK,
+                },
+            ));
+        });
+}
+
+/// Highlights the hovered buttons
+struct ButtonPlugin;
+
+impl Plugin for ButtonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(highlight_buttons);
+    }
+}
+
+fn highlight_buttons(
+    mut buttons: Query<(&Interaction, &mut UiColor), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut color) in &mut buttons {
+        let hex = match interaction {
+            Interaction::Hovered | Interaction::Clicked => HOVERED_BUTTON_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        };
+        *color = Color::hex(hex).unwrap().into();
+    }
+}
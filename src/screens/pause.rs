@@ -0,0 +1,91 @@
+use bevy::{app::AppExit, prelude::*, window::WindowFocused};
+use iyes_loopless::prelude::*;
+
+use crate::{controls::Controls, despawn::despawn, Fonts, GameState};
+
+use super::{spawn_button, spawn_screen};
+
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct PauseScreen;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum Action {
+    Resume,
+    Restart,
+    Quit,
+}
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(GameState::Paused, Self::spawn)
+            .add_exit_system(GameState::Paused, despawn::<PauseScreen>)
+            .add_system(Self::pause.run_in_state(GameState::Playing))
+            .add_system(Self::resume.run_in_state(GameState::Paused))
+            .add_system(Self::click.run_in_state(GameState::Paused));
+    }
+}
+
+impl Plugin {
+    /// Pauses on request, or when the window loses the focus
+    fn pause(
+        mut commands: Commands,
+        controls: Res<Controls>,
+        mut focus: EventReader<WindowFocused>,
+    ) {
+        if controls.pause || focus.iter().any(|f| !f.focused) {
+            commands.insert_resource(NextState(GameState::Paused));
+        }
+    }
+
+    fn resume(mut commands: Commands, controls: Res<Controls>) {
+        if controls.pause {
+            commands.insert_resource(NextState(GameState::Playing));
+        }
+    }
+
+    fn click(
+        mut commands: Commands,
+        buttons: Query<(&Interaction, &Action), Changed<Interaction>>,
+        mut exit: EventWriter<AppExit>,
+    ) {
+        for (interaction, action) in &buttons {
+            if *interaction != Interaction::Clicked {
+                continue;
+            }
+            match action {
+                Action::Resume => commands.insert_resource(NextState(GameState::Playing)),
+                Action::Restart => commands.insert_resource(NextState(GameState::Ready)),
+                Action::Quit => exit.send(AppExit),
+            }
+        }
+    }
+
+    fn spawn(mut commands: Commands, fonts: Res<Fonts>) {
+        spawn_screen::<PauseScreen>(&mut commands, |parent| {
+            parent.spawn_bundle(
+                TextBundle::from_section(
+                    "Paused",
+                    TextStyle {
+                        font: fonts.main.clone(),
+                        font_size: 100.0,
+                        color: Color::BLACK,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(20.0)),
+                    ..Default::default()
+                }),
+            );
+            for (action, label) in [
+                (Action::Resume, "Resume"),
+                (Action::Restart, "Restart"),
+                (Action::Quit, "Quit"),
+            ] {
+                spawn_button(parent, &fonts.main, label, action);
+            }
+        });
+    }
+}
@@ -1,9 +1,9 @@
 use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 
-use crate::{despawn::despawn, Fonts, GameState};
+use crate::{despawn::despawn, highscore::HighScores, Fonts, GameState};
 
-use super::spawn_screen;
+use super::{settings::SettingsOpen, spawn_screen};
 
 #[derive(Debug, Clone, Copy, Default, Component)]
 struct ReadyScreen;
@@ -15,7 +15,12 @@ impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_enter_system(GameState::Ready, Self::spawn)
             .add_exit_system(GameState::Ready, despawn::<ReadyScreen>)
-            .add_system(Self::start.run_in_state(GameState::Ready));
+            .add_system(
+                Self::start
+                    .run_in_state(GameState::Ready)
+                    .run_unless_resource_exists::<SettingsOpen>(),
+            )
+            .add_system(Self::hide.run_in_state(GameState::Ready));
     }
 }
 
@@ -23,10 +28,26 @@ impl Plugin {
     fn start(mut commands: Commands, input: Res<Input<KeyCode>>) {
         if input.just_pressed(KeyCode::Space) {
             commands.insert_resource(NextState(GameState::Playing));
+        } else if input.just_pressed(KeyCode::Tab) {
+            commands.insert_resource(SettingsOpen);
         }
     }
 
-    fn spawn(mut commands: Commands, fonts: Res<Fonts>) {
+    /// Hides the screen while the settings are shown
+    fn hide(open: Option<Res<SettingsOpen>>, mut screens: Query<&mut Style, With<ReadyScreen>>) {
+        let display = if open.is_some() {
+            Display::None
+        } else {
+            Display::Flex
+        };
+        for mut style in &mut screens {
+            if style.display != display {
+                style.display = display;
+            }
+        }
+    }
+
+    fn spawn(mut commands: Commands, fonts: Res<Fonts>, high_scores: Res<HighScores>) {
         spawn_screen::<ReadyScreen>(&mut commands, |parent| {
             parent.spawn_bundle(
                 TextBundle::from_section(
@@ -42,6 +63,58 @@ impl Plugin {
                     ..Default::default()
                 }),
             );
+            parent.spawn_bundle(
+                TextBundle::from_section(
+                    "Press <tab> to change the controls",
+                    TextStyle {
+                        font: fonts.main.clone(),
+                        color: Color::DARK_GRAY,
+                        font_size: 30.0,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..Default::default()
+                }),
+            );
+            if high_scores.entries().is_empty() {
+                return;
+            }
+            let text_style = TextStyle {
+                font: fonts.main.clone(),
+                color: Color::BLACK,
+                font_size: 25.0,
+            };
+            parent.spawn_bundle(
+                TextBundle::from_section(
+                    "High scores",
+                    TextStyle {
+                        font_size: 40.0,
+                        ..text_style.clone()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(30.0), Val::Px(10.0)),
+                    ..Default::default()
+                }),
+            );
+            for (rank, entry) in high_scores.entries().iter().enumerate() {
+                let seconds = entry.duration.as_secs();
+                parent.spawn_bundle(TextBundle::from_section(
+                    format!(
+                        "{}. {}  {:.0}  {}:{:02}  {} kills  {}  seed {}",
+                        rank + 1,
+                        entry.name,
+                        entry.score,
+                        seconds / 60,
+                        seconds % 60,
+                        entry.kills,
+                        entry.date,
+                        entry.seed,
+                    ),
+                    text_style.clone(),
+                ));
+            }
         });
     }
 }
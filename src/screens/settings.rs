@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{
+    controls::{Action, Bindings, BindingsFile, Devices},
+    data,
+    despawn::despawn,
+    Fonts, GameState,
+};
+
+use super::{spawn_button, spawn_screen};
+
+#[derive(Debug, Clone, Copy, Default, Component)]
+struct SettingsScreen;
+
+/// Text listing the bindings of an action
+#[derive(Debug, Clone, Copy, Component)]
+struct BindingText(Action);
+
+#[derive(Debug, Clone, Copy, Component)]
+enum Choice {
+    Rebind(Action),
+    ResetDefaults,
+    Back,
+}
+
+/// Shows the settings screen, over the ready screen
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct SettingsOpen;
+
+/// Action waiting for the player to press its new input
+#[derive(Debug, Clone, Copy)]
+struct Rebinding(Action);
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_exit_system(GameState::Ready, despawn::<SettingsScreen>)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Ready)
+                    .run_if_resource_exists::<SettingsOpen>()
+                    .with_system(Self::spawn.run_if(Self::opened))
+                    .with_system(Self::click)
+                    .with_system(Self::capture.run_if_resource_exists::<Rebinding>())
+                    .with_system(Self::update_texts)
+                    .into(),
+            )
+            .add_system(
+                Self::close
+                    .run_in_state(GameState::Ready)
+                    .run_unless_resource_exists::<SettingsOpen>()
+                    .run_if(Self::shown),
+            );
+    }
+}
+
+impl Plugin {
+    fn opened(open: Res<SettingsOpen>) -> bool {
+        open.is_added()
+    }
+
+    fn shown(screens: Query<(), With<SettingsScreen>>) -> bool {
+        !screens.is_empty()
+    }
+
+    fn click(
+        mut commands: Commands,
+        buttons: Query<(&Interaction, &Choice), Changed<Interaction>>,
+    ) {
+        for (interaction, button) in &buttons {
+            if *interaction != Interaction::Clicked {
+                continue;
+            }
+            match *button {
+                Choice::Rebind(action) => commands.insert_resource(Rebinding(action)),
+                Choice::ResetDefaults => commands.insert_resource(Bindings::default()),
+                Choice::Back => commands.remove_resource::<SettingsOpen>(),
+            }
+        }
+    }
+
+    /// Binds the next pressed input to the action being rebound
+    ///
+    /// Starts on the frame after the click, so that the click itself is not taken as the input.
+    fn capture(
+        mut commands: Commands,
+        rebinding: Res<Rebinding>,
+        mut bindings: ResMut<Bindings>,
+        devices: Devices,
+    ) {
+        if let Some(binding) = devices.any_just_pressed() {
+            bindings.rebind(rebinding.0, binding);
+            commands.remove_resource::<Rebinding>();
+        }
+    }
+
+    fn update_texts(
+        bindings: Res<Bindings>,
+        rebinding: Option<Res<Rebinding>>,
+        mut texts: Query<(&mut Text, &BindingText)>,
+    ) {
+        for (mut text, BindingText(action)) in &mut texts {
+            text.sections[0].value = match &rebinding {
+                Some(rebinding) if rebinding.0 == *action => String::from("Press an input..."),
+                _ => bindings
+                    .get(*action)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+        }
+    }
+
+    /// Saves the bindings when closing the screen
+    fn close(
+        mut commands: Commands,
+        bindings: Res<Bindings>,
+        file: Res<BindingsFile>,
+        screens: Query<Entity, With<SettingsScreen>>,
+    ) {
+        for screen in &screens {
+            commands.entity(screen).despawn_recursive();
+        }
+        commands.remove_resource::<Rebinding>();
+        if let Some(path) = &file.0 {
+            if let Err(err) = data::save_ron(&*bindings, path) {
+                error!("Cannot save bindings to {}: {err}", path.display());
+            }
+        }
+    }
+
+    fn spawn(mut commands: Commands, fonts: Res<Fonts>) {
+        let text_style = TextStyle {
+            font: fonts.main.clone(),
+            color: Color::BLACK,
+            font_size: 30.0,
+        };
+        spawn_screen::<SettingsScreen>(&mut commands, |parent| {
+            parent.spawn_bundle(
+                TextBundle::from_section(
+                    "Controls",
+                    TextStyle {
+                        font_size: 80.0,
+                        ..text_style.clone()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(20.0)),
+                    ..Default::default()
+                }),
+            );
+            for action in Action::ALL {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        color: Color::NONE.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn_bundle(
+                            TextBundle::from_section(format!("{action:?}"), text_style.clone())
+                                .with_style(Style {
+                                    size: Size::new(Val::Px(250.0), Val::Auto),
+                                    ..Default::default()
+                                }),
+                        );
+                        parent
+                            .spawn_bundle(
+                                TextBundle::from_section("", text_style.clone()).with_style(
+                                    Style {
+                                        size: Size::new(Val::Px(600.0), Val::Auto),
+                                        ..Default::default()
+                                    },
+                                ),
+                            )
+                            .insert(BindingText(action));
+                        spawn_button(parent, &fonts.main, "Rebind", Choice::Rebind(action));
+                    });
+            }
+            spawn_button(parent, &fonts.main, "Defaults", Choice::ResetDefaults);
+            spawn_button(parent, &fonts.main, "Back", Choice::Back);
+        });
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, transform::TransformSystem};
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+
+/// Name of the fixed timestep stage running the gameplay
+///
+/// Gameplay systems are added to it with `add_fixed_timestep_system(simulation::LABEL, <step>, ...)`
+/// where `<step>` is one of [`INPUT`], [`UPDATE`] or [`LATE`]. All of them must use [`STEP`] as
+/// their delta time so that the outcome doesn't depend on the frame rate.
+pub const LABEL: &str = "simulation";
+
+/// Duration of a simulation step
+pub const STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Sub-stage applying the player inputs
+pub const INPUT: usize = 0;
+
+/// Sub-stage running the gameplay
+pub const UPDATE: usize = 1;
+
+/// Sub-stage reacting to the outcome of the step
+pub const LATE: usize = 2;
+
+/// Number of simulation steps since the start of the run
+#[derive(Debug, Clone, Copy, Default, Deref)]
+pub struct Tick(u64);
+
+/// Label of the system incrementing the [`Tick`], at the beginning of each step
+#[derive(Debug, Clone, Copy, SystemLabel)]
+pub struct Advance;
+
+/// Translation of an entity at the two last simulation steps
+///
+/// Between two steps, the entity is rendered somewhere in between, so that the motion looks smooth
+/// whatever the frame rate is.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct Interpolated {
+    previous: Option<Vec3>,
+    current: Vec3,
+}
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        // Sub-stages are single threaded, so that the systems always run in the same order
+        let stage = FixedTimestepStage::from_stage(STEP, LABEL, SystemStage::single_threaded())
+            .with_stage(SystemStage::single_threaded())
+            .with_stage(SystemStage::single_threaded());
+
+        app.init_resource::<Tick>()
+            .add_stage_before(CoreStage::Update, LABEL, stage)
+            .add_exit_system(GameState::Ready, Self::reset_tick)
+            .add_fixed_timestep_system(
+                LABEL,
+                INPUT,
+                Self::advance
+                    .run_in_state(GameState::Playing)
+                    .label(Advance),
+            )
+            .add_fixed_timestep_system(LABEL, INPUT, Self::snapshot)
+            .add_system_to_stage(CoreStage::First, Self::restore)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                Self::interpolate.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+impl Plugin {
+    fn reset_tick(mut tick: ResMut<Tick>) {
+        tick.0 = 0;
+    }
+
+    fn advance(mut tick: ResMut<Tick>) {
+        tick.0 += 1;
+    }
+
+    fn snapshot(mut entities: Query<(&Transform, &mut Interpolated)>) {
+        for (transform, mut interpolated) in &mut entities {
+            interpolated.previous = Some(transform.translation);
+        }
+    }
+
+    fn interpolate(
+        timesteps: Res<FixedTimesteps>,
+        mut entities: Query<(&mut Transform, &mut Interpolated)>,
+    ) {
+        let progress = timesteps
+            .get(LABEL)
+            .map_or(1.0, |info| info.overstep_percentage() as f32);
+        for (mut transform, mut interpolated) in &mut entities {
+            interpolated.current = transform.translation;
+            if let Some(previous) = interpolated.previous {
+                transform.translation = previous.lerp(interpolated.current, progress);
+            }
+        }
+    }
+
+    fn restore(mut entities: Query<(&mut Transform, &Interpolated)>) {
+        for (mut transform, interpolated) in &mut entities {
+            if interpolated.previous.is_some() {
+                transform.translation = interpolated.current;
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::marker::PhantomData;
+
+use bevy::{prelude::*, utils::HashMap};
+use iyes_loopless::prelude::*;
+
+use crate::{field::Position, simulation};
+
+/// Entities having the component `C`, bucketed by the cell of the field they are over
+///
+/// Rebuilt at the beginning of each simulation step.
+pub struct SpatialGrid<C> {
+    buckets: HashMap<Position, Vec<(Entity, Vec2)>>,
+    marker: PhantomData<fn() -> C>,
+}
+
+impl<C> Default for SpatialGrid<C> {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<C> SpatialGrid<C> {
+    /// Entities (with their world coordinates) that are within `radius` of `center`
+    pub fn query(&self, center: Vec2, radius: f32) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let min = *Position::from_world(center - radius);
+        let max = *Position::from_world(center + radius);
+        let radius_squared = radius * radius;
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| Position(IVec2::new(x, y))))
+            .filter_map(|position| self.buckets.get(&position))
+            .flatten()
+            .copied()
+            .filter(move |(_, coord)| coord.distance_squared(center) <= radius_squared)
+    }
+}
+
+/// Label of the systems rebuilding the spatial grids
+#[derive(Debug, Clone, Copy, SystemLabel)]
+pub struct Indexing;
+
+pub trait SpatialGridApp {
+    /// Maintains a [`SpatialGrid`] of the entities having the component `C`
+    fn add_spatial_grid<C: Component>(&mut self) -> &mut Self;
+}
+
+impl SpatialGridApp for App {
+    fn add_spatial_grid<C: Component>(&mut self) -> &mut Self {
+        self.init_resource::<SpatialGrid<C>>()
+            .add_fixed_timestep_system(
+                simulation::LABEL,
+                simulation::INPUT,
+                index::<C>.label(Indexing),
+            )
+    }
+}
+
+fn index<C: Component>(
+    mut grid: ResMut<SpatialGrid<C>>,
+    entities: Query<(Entity, &Transform), With<C>>,
+) {
+    for bucket in grid.buckets.values_mut() {
+        bucket.clear();
+    }
+    for (entity, transform) in &entities {
+        let coord = transform.translation.truncate();
+        grid.buckets
+            .entry(Position::from_world(coord))
+            .or_default()
+            .push((entity, coord));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct Marker;
+
+    #[test]
+    fn query_returns_entities_within_radius_across_cells() {
+        let mut grid = SpatialGrid::<Marker>::default();
+        let near = Entity::from_raw(1);
+        let far = Entity::from_raw(2);
+        for (entity, coord) in [(near, Vec2::new(1.45, 0.0)), (far, Vec2::new(2.0, 2.0))] {
+            grid.buckets
+                .entry(Position::from_world(coord))
+                .or_default()
+                .push((entity, coord));
+        }
+
+        let found: Vec<Entity> = grid
+            .query(Vec2::new(1.0, 0.2), 0.6)
+            .map(|(e, _)| e)
+            .collect();
+
+        assert_eq!(found, vec![near]);
+    }
+}
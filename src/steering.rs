@@ -0,0 +1,207 @@
+//! Steering behaviours of the enemies, blended with weights chosen by each kind of enemy
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Longest time ahead for which the position of the combine is predicted
+const MAX_PREDICTION: f32 = 3.0;
+
+/// Refinements of the time at which an enemy can meet the combine
+const INTERCEPT_ITERATIONS: usize = 4;
+
+/// Distance under which enemies push each other away
+pub const SEPARATION_RADIUS: f32 = 0.8;
+
+/// Distance under which enemies gather
+pub const COHESION_RADIUS: f32 = 2.5;
+
+/// Weights of the steering behaviours of a kind of enemy
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Steering {
+    /// Toward the current position of the combine
+    pub seek: f32,
+    /// Toward the position where the combine can be met
+    pub intercept: f32,
+    /// Away from the nearby enemies
+    pub separation: f32,
+    /// Toward the centre of the nearby enemies
+    pub cohesion: f32,
+    /// Circles around the combine before diving at it by seeking and intercepting
+    pub orbit: Option<Orbit>,
+}
+
+impl Default for Steering {
+    fn default() -> Self {
+        Self {
+            seek: 1.0,
+            intercept: 0.0,
+            separation: 0.0,
+            cohesion: 0.0,
+            orbit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Orbit {
+    /// Distance from the combine, in cells
+    pub radius: f32,
+    /// Time spent circling before the dive, in seconds
+    pub time: f32,
+}
+
+/// Time left circling around the combine, ticking only once close to the orbit
+#[derive(Debug, Clone, Component, Deref, DerefMut)]
+pub struct Orbiting(Timer);
+
+impl Orbiting {
+    pub fn new(orbit: &Orbit) -> Self {
+        Self(Timer::new(Duration::from_secs_f32(orbit.time), false))
+    }
+}
+
+impl Steering {
+    /// Direction toward the combine, blending seek and intercept, or around it while `orbiting`
+    ///
+    /// `predict` gives the position of the combine for a time ahead.
+    pub fn pursue(
+        &self,
+        position: Vec2,
+        speed: f32,
+        predict: impl Fn(f32) -> Vec2,
+        orbiting: Option<&mut Orbiting>,
+        delta: Duration,
+    ) -> Vec2 {
+        let target = predict(0.0);
+        if let (Some(orbit), Some(orbiting)) = (self.orbit, orbiting) {
+            if !orbiting.finished() {
+                if position.distance(target) < orbit.radius * 1.5 {
+                    orbiting.tick(delta);
+                }
+                return orbit_around(position, target, orbit.radius);
+            }
+        }
+        let toward = (target - position).normalize_or_zero();
+        let meeting = intercept(position, speed, predict);
+        let toward_meeting = (meeting - position).normalize_or_zero();
+        (toward * self.seek + toward_meeting * self.intercept).normalize_or_zero()
+    }
+
+    /// Direction keeping away from the closest enemies while staying with the others nearby, given
+    /// the positions of the enemies around
+    pub fn flock(&self, position: Vec2, neighbours: impl Iterator<Item = Vec2>) -> Vec2 {
+        if self.separation == 0.0 && self.cohesion == 0.0 {
+            return Vec2::ZERO;
+        }
+        let mut push = Vec2::ZERO;
+        let mut centre = Vec2::ZERO;
+        let mut count = 0;
+        for neighbour in neighbours {
+            let offset = position - neighbour;
+            let distance = offset.length();
+            if distance == 0.0 || distance > COHESION_RADIUS {
+                continue;
+            }
+            if distance < SEPARATION_RADIUS {
+                push += offset / (distance * distance);
+            } else {
+                centre += neighbour;
+                count += 1;
+            }
+        }
+        let gather = if count > 0 {
+            (centre / count as f32 - position).normalize_or_zero()
+        } else {
+            Vec2::ZERO
+        };
+        push.clamp_length_max(1.0) * self.separation + gather * self.cohesion
+    }
+}
+
+/// Point where an enemy at `position` flying at `speed` can meet the combine, whose position for a
+/// time ahead is given by `predict`
+pub fn intercept(position: Vec2, speed: f32, predict: impl Fn(f32) -> Vec2) -> Vec2 {
+    if speed <= 0.0 {
+        return predict(0.0);
+    }
+    let mut time = 0.0;
+    for _ in 0..INTERCEPT_ITERATIONS {
+        time = (position.distance(predict(time)) / speed).min(MAX_PREDICTION);
+    }
+    predict(time)
+}
+
+/// Direction circling counterclockwise around `center`, drifting back to `radius` from it
+pub fn orbit_around(position: Vec2, center: Vec2, radius: f32) -> Vec2 {
+    let offset = position - center;
+    let outward = offset.normalize_or_zero();
+    let correction = (radius - offset.length()).clamp(-2.0, 2.0);
+    (outward.perp() + outward * correction).normalize_or_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intercepts_ahead_of_a_moving_target() {
+        let predict = |time: f32| Vec2::new(time * 1.5, 5.0);
+        let meeting = intercept(Vec2::ZERO, 3.0, predict);
+
+        assert!(meeting.x > 1.0);
+        let enemy_time = meeting.length() / 3.0;
+        assert!((enemy_time * 1.5 - meeting.x).abs() < 0.1);
+    }
+
+    #[test]
+    fn orbits_around_then_dives() {
+        let steering = Steering {
+            orbit: Some(Orbit {
+                radius: 2.0,
+                time: 1.0,
+            }),
+            ..Default::default()
+        };
+        let predict = |_: f32| Vec2::ZERO;
+        let mut orbiting = Orbiting::new(&steering.orbit.unwrap());
+        let position = Vec2::new(2.0, 0.0);
+
+        let circling = steering.pursue(
+            position,
+            1.0,
+            predict,
+            Some(&mut orbiting),
+            Duration::from_millis(600),
+        );
+        assert!(circling.x.abs() < 0.01 && circling.y > 0.99);
+
+        orbiting.tick(Duration::from_millis(600));
+        let diving = steering.pursue(position, 1.0, predict, Some(&mut orbiting), Duration::ZERO);
+        assert!(diving.x < -0.99);
+    }
+
+    #[test]
+    fn separates_from_close_enemies_and_gathers_with_far_ones() {
+        let steering = Steering {
+            seek: 0.0,
+            separation: 1.0,
+            cohesion: 1.0,
+            ..Default::default()
+        };
+        assert!(
+            steering
+                .flock(Vec2::ZERO, [Vec2::new(0.3, 0.0)].into_iter())
+                .x
+                < 0.0
+        );
+        assert!(
+            steering
+                .flock(Vec2::ZERO, [Vec2::new(2.0, 0.0)].into_iter())
+                .x
+                > 0.0
+        );
+    }
+}
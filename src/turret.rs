@@ -1,22 +1,33 @@
-use std::f32::consts::PI;
-use std::time::Duration;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::Duration,
+};
 
-use bevy::prelude::*;
-use itertools_num::linspace;
+use bevy::{prelude::*, reflect::TypeUuid, utils::HashSet};
 use iyes_loopless::prelude::*;
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use serde::Deserialize;
 
 use crate::{
+    collision::{Collider, Collision, CollisionApp},
     combine::{Harvested, Harvester},
+    controls::Controls,
+    data::RonAssetApp,
     despawn::{despawn, DespawnTimer},
-    enemy::Enemy,
-    mouse::Cursor,
-    movement::Velocity,
+    enemy::{Enemy, EnemyKilled, Kind, Projectile},
+    field::Cell,
+    health::Health,
+    movement::{self, Velocity},
+    rng::GameRng,
+    simulation::{self, Interpolated},
     GameState,
 };
 
 pub const MAX_AMMO: u32 = 20;
 
+/// Number of columns of `sprites/item.png`
+pub const ITEM_COLUMNS: usize = 6;
+
 #[derive(Debug, Default)]
 struct AssetTable {
     turret: Handle<TextureAtlas>,
@@ -24,40 +35,301 @@ struct AssetTable {
     item: Handle<TextureAtlas>,
 }
 
+/// How long a weapon lasts once its item is collected
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum Budget {
+    #[default]
+    Unlimited,
+    /// Seconds
+    Time(f32),
+    Shots(u32),
+}
+
+impl Budget {
+    fn charge(&self) -> Charge {
+        match *self {
+            Self::Unlimited => Charge::Unlimited,
+            Self::Time(seconds) => Charge::Time {
+                left: seconds,
+                full: seconds,
+            },
+            Self::Shots(count) => Charge::Shots {
+                left: count,
+                full: count,
+            },
+        }
+    }
+}
+
+/// Firing pattern of the turret, as described in the [`Weapons`]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Weapon {
+    /// Seconds between two shots
+    interval: f32,
+    /// Bullets fired by each shot
+    count: usize,
+    /// Angle, in radians, over which the bullets of a shot are fanned out
+    spread: f32,
+    /// Direction of the shot, in radians, relative to the aim
+    angle: f32,
+    /// Relative to the speed of the round
+    speed: f32,
+    /// Seconds before the bullets vanish
+    lifetime: f32,
+    /// Relative to the damage of the round
+    damage: u32,
+    /// Number of enemies the bullets go through, on top of the pierce of the round
+    pierce: u32,
+    /// Column of its item in `sprites/item.png`
+    sprite: usize,
+    budget: Budget,
+    /// Whether collecting its item while it is active adds to what is left of it, rather than
+    /// refilling it
+    stacks: bool,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self {
+            interval: 0.2,
+            count: 1,
+            spread: 0.0,
+            angle: 0.0,
+            speed: 10.0,
+            lifetime: 5.0,
+            damage: 1,
+            pierce: 0,
+            sprite: 0,
+            budget: Budget::Unlimited,
+            stacks: false,
+        }
+    }
+}
+
+/// Weapons of the turret, loaded from `assets/balance/default.weapons.ron`
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "ceb46009-d31b-4445-bcaf-2e8793045ca8"]
+pub struct Weapons {
+    /// Weapon used when no item is active
+    base: String,
+    weapons: BTreeMap<String, Weapon>,
+}
+
+impl Weapons {
+    pub fn get(&self, name: &str) -> Option<&Weapon> {
+        self.weapons.get(name)
+    }
+
+    /// Weapon of the turret with the `power_up`
+    fn active(&self, power_up: &PowerUp) -> Option<&Weapon> {
+        self.get(power_up.weapon.as_deref().unwrap_or(&self.base))
+    }
+}
+
+#[derive(Debug, Default)]
+struct WeaponsHandle(Handle<Weapons>);
+
+/// What is left of the weapon of an item before the turret goes back to the base weapon: seconds
+/// or shots left, out of `full`
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum TurretMode {
-    Base,
-    Fast,
-    Shotgun,
-    Split,
-    Reverse,
-    Nuke,
+pub enum Charge {
+    Unlimited,
+    Time { left: f32, full: f32 },
+    Shots { left: u32, full: u32 },
+}
+
+impl Charge {
+    /// Part of the charge left, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        match *self {
+            Self::Unlimited => 1.0,
+            Self::Time { left, full } => (left / full).clamp(0.0, 1.0),
+            Self::Shots { left, full } => left as f32 / full.max(1) as f32,
+        }
+    }
+
+    fn is_spent(&self) -> bool {
+        match *self {
+            Self::Unlimited => false,
+            Self::Time { left, .. } => left <= 0.0,
+            Self::Shots { left, .. } => left == 0,
+        }
+    }
+
+    /// Adds `other` to what is left, the bar being full again when it overflows
+    fn stack(self, other: Charge) -> Self {
+        match (self, other) {
+            (Self::Time { left, full }, Self::Time { left: added, .. }) => Self::Time {
+                left: left + added,
+                full: full.max(left + added),
+            },
+            (Self::Shots { left, full }, Self::Shots { left: added, .. }) => Self::Shots {
+                left: left + added,
+                full: full.max(left + added),
+            },
+            _ => other,
+        }
+    }
+}
+
+/// Weapon of the last collected item, with what is left of it
+#[derive(Debug, Clone, PartialEq, Component)]
+pub struct PowerUp {
+    /// Name of the weapon in the [`Weapons`], the base one when `None`
+    pub weapon: Option<String>,
+    /// Column of its item in `sprites/item.png`
+    pub sprite: usize,
+    pub charge: Charge,
+}
+
+impl Default for PowerUp {
+    fn default() -> Self {
+        Self {
+            weapon: None,
+            sprite: 0,
+            charge: Charge::Unlimited,
+        }
+    }
+}
+
+impl PowerUp {
+    /// Switches to the weapon named `name`, or refills or stacks its charge when already active
+    fn collect(&mut self, name: &str, weapon: &Weapon) {
+        if self.weapon.as_deref() == Some(name) && weapon.stacks {
+            self.charge = self.charge.stack(weapon.budget.charge());
+        } else {
+            *self = Self {
+                weapon: Some(name.to_string()),
+                sprite: weapon.sprite,
+                charge: weapon.budget.charge(),
+            };
+        }
+    }
+
+    fn spend_time(&mut self, seconds: f32) {
+        if let Charge::Time { left, .. } = &mut self.charge {
+            *left -= seconds;
+        }
+        self.expire();
+    }
+
+    fn spend_shot(&mut self) {
+        if let Charge::Shots { left, .. } = &mut self.charge {
+            *left = left.saturating_sub(1);
+        }
+        self.expire();
+    }
+
+    fn expire(&mut self) {
+        if self.charge.is_spent() {
+            *self = Self::default();
+        }
+    }
+}
+
+/// Kind of round fired by the turret, depending on the crop it is made of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Round {
+    #[default]
+    Regular,
+    /// Goes through enemies
+    Piercing,
+    /// Bigger, slower and deals more damage
+    Heavy,
+}
+
+impl Round {
+    /// Number of enemies a bullet goes through before stopping
+    fn pierce(&self) -> u32 {
+        match self {
+            Self::Piercing => 2,
+            _ => 0,
+        }
+    }
+
+    /// Size of a bullet, relative to a regular one
+    fn scale(&self) -> f32 {
+        match self {
+            Self::Heavy => 1.8,
+            _ => 1.0,
+        }
+    }
+
+    fn speed(&self) -> f32 {
+        match self {
+            Self::Heavy => 0.7,
+            _ => 1.0,
+        }
+    }
+
+    fn damage(&self) -> u32 {
+        match self {
+            Self::Heavy => 2,
+            _ => 1,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Component)]
 struct Turret {
+    /// Harvester the turret is mounted on, loading it with the rounds of its harvests
+    harvester: Entity,
     cool_down: Timer,
-    mode: TurretMode,
+    /// Round of the shot being fired
+    round: Round,
 }
 
-impl Default for Turret {
-    fn default() -> Self {
+impl Turret {
+    fn new(harvester: Entity) -> Self {
         Self {
+            harvester,
             cool_down: Timer::new(Duration::ZERO, false),
-            mode: TurretMode::Base,
+            round: Round::default(),
         }
     }
 }
 
-#[derive(Debug, Clone, Default, Component, Deref, DerefMut)]
-pub struct Ammo(u32);
+/// Rounds of the turret, the last harvested being fired first
+#[derive(Debug, Clone, Default, Component)]
+pub struct Ammo(VecDeque<Round>);
+
+impl Ammo {
+    pub fn count(&self) -> u32 {
+        self.0.len() as u32
+    }
+
+    /// Round of the next shot
+    pub fn loaded(&self) -> Option<Round> {
+        self.0.back().copied()
+    }
+
+    /// Loads a round, dropping the oldest one when full
+    fn load(&mut self, round: Round) {
+        if self.0.len() as u32 >= MAX_AMMO {
+            self.0.pop_front();
+        }
+        self.0.push_back(round);
+    }
+}
 
 #[derive(Debug, Clone, Component, Default)]
-struct Bullet;
+struct Bullet {
+    /// Number of enemies the bullet can still go through
+    pierce: u32,
+    damage: u32,
+    /// Enemies already hit, which the bullet goes through
+    hit: Vec<Entity>,
+}
+
+pub struct Shot;
+
+pub struct ItemCollected;
 
 #[derive(Debug, Clone, Component)]
 pub struct Item {
-    mode: TurretMode,
+    /// Name of the weapon in the [`Weapons`]
+    weapon: String,
 }
 
 #[derive(Default)]
@@ -65,82 +337,132 @@ pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<AssetTable>()
+        app.add_ron_asset::<Weapons>(&["weapons.ron"])
+            .init_resource::<AssetTable>()
+            .init_resource::<WeaponsHandle>()
+            .add_event::<Shot>()
+            .add_event::<ItemCollected>()
+            .add_collision::<Bullet, Enemy>()
+            .add_collision::<Bullet, Cell>()
+            .add_collision::<Bullet, Projectile>()
+            .add_collision::<Harvester, Item>()
             .add_startup_system(Self::load_assets)
+            .add_startup_system(Self::load_weapons)
             .add_enter_system(GameState::Ready, despawn::<Turret>)
             .add_enter_system(GameState::Ready, despawn::<Item>)
-            .add_enter_system(GameState::Playing, Self::spawn_turret)
-            .add_system_to_stage(
-                CoreStage::PostUpdate,
-                Self::aim.run_in_state(GameState::Playing),
-            )
-            .add_system_set(
+            .add_enter_system(GameState::Ready, despawn::<Bullet>)
+            .add_exit_system(GameState::Ready, Self::spawn_turret)
+            .add_fixed_timestep_system_set(
+                simulation::LABEL,
+                simulation::UPDATE,
                 ConditionSet::new()
                     .run_in_state(GameState::Playing)
                     .with_system(Self::spawn_bullet.run_if(Self::shoot))
                     .with_system(Self::kill_enemy)
+                    .with_system(Self::hit_obstacle)
                     .with_system(Self::collect_item)
                     .into(),
             )
-            .add_system_to_stage(
-                CoreStage::PostUpdate,
-                Self::reload.run_in_state(GameState::Playing),
+            .add_fixed_timestep_system_set(
+                simulation::LABEL,
+                simulation::LATE,
+                ConditionSet::new()
+                    .run_in_state(GameState::Playing)
+                    .with_system(Self::aim)
+                    .with_system(Self::reload)
+                    // After the shots, so that the last shot of a timed weapon is always fired with
+                    // it
+                    .with_system(Self::drain)
+                    // After the bullets hitting enemies, so that a bullet spent on an enemy never
+                    // also shoots a projectile down
+                    .with_system(Self::shoot_down)
+                    .into(),
             );
     }
 }
 
 impl Plugin {
-    fn reload(mut harvests: EventReader<Harvested>, mut ammos: Query<&mut Ammo>) {
-        const AMMO_PER_CROP_CELL: u32 = 1;
-        let delta_ammo = harvests.iter().count() as u32 * AMMO_PER_CROP_CELL;
-        if delta_ammo == 0 {
-            return;
-        }
-        for mut ammo in &mut ammos {
-            **ammo = (**ammo + delta_ammo).min(MAX_AMMO);
+    fn reload(mut harvests: EventReader<Harvested>, mut turrets: Query<(&Turret, &mut Ammo)>) {
+        for harvested in harvests.iter() {
+            for (turret, mut ammo) in &mut turrets {
+                if turret.harvester != harvested.by {
+                    continue;
+                }
+                for _ in 0..harvested.level {
+                    ammo.load(harvested.species.round());
+                }
+            }
         }
     }
 
     fn shoot(
-        input: Res<Input<MouseButton>>,
-        mut turrets: Query<(&mut Turret, &mut Ammo)>,
-        time: Res<Time>,
+        controls: Res<Controls>,
+        handle: Res<WeaponsHandle>,
+        weapons: Res<Assets<Weapons>>,
+        mut turrets: Query<(&mut Turret, &mut Ammo, &PowerUp)>,
     ) -> bool {
-        let (mut turret, mut ammo) = match turrets.get_single_mut() {
+        let (mut turret, mut ammo, power_up) = match turrets.get_single_mut() {
             Ok(t) => t,
             Err(_) => return false,
         };
-        turret.cool_down.tick(time.delta());
-        if turret.cool_down.finished() && input.pressed(MouseButton::Left) && **ammo > 0 {
-            **ammo -= 1;
-            turret.cool_down = Timer::new(Duration::from_secs_f32(0.2), false);
+        let weapon = match weapons.get(&handle.0).and_then(|w| w.active(power_up)) {
+            Some(weapon) => weapon,
+            None => return false,
+        };
+        turret.cool_down.tick(simulation::STEP);
+        if !turret.cool_down.finished() || !controls.fire {
+            return false;
+        }
+        if let Some(round) = ammo.0.pop_back() {
+            turret.round = round;
+            // The hot-reloaded weapons may have invalid values
+            let interval = Duration::from_secs_f32(weapon.interval.max(0.0));
+            turret.cool_down = Timer::new(interval, false);
             true
         } else {
             false
         }
     }
 
+    /// Drains the timed weapons, going back to the base one once spent
+    fn drain(mut power_ups: Query<&mut PowerUp>) {
+        for mut power_up in &mut power_ups {
+            if let Charge::Time { .. } = power_up.charge {
+                power_up.spend_time(simulation::STEP.as_secs_f32());
+            }
+        }
+    }
+
     fn spawn_bullet(
         mut commands: Commands,
-        mut turrets: Query<(&Transform, &mut Turret), With<Turret>>,
+        mut turrets: Query<(&Transform, &Turret, &mut PowerUp)>,
         assets: Res<AssetTable>,
+        handle: Res<WeaponsHandle>,
+        weapons: Res<Assets<Weapons>>,
+        controls: Res<Controls>,
+        mut shots: EventWriter<Shot>,
     ) {
-        for (turret_transform, mut turret) in &mut turrets {
+        let weapons = match weapons.get(&handle.0) {
+            Some(weapons) => weapons,
+            None => return,
+        };
+        for (turret_transform, turret, mut power_up) in &mut turrets {
+            let weapon = match weapons.active(&power_up) {
+                Some(weapon) => weapon,
+                None => continue,
+            };
             let mut transform = *turret_transform;
             transform.translation -= Vec3::Z * 0.5; // To be rendered behind the turret
+            if let Some(direction) =
+                (controls.aim - transform.translation.truncate()).try_normalize()
+            {
+                transform.rotation =
+                    Quat::from_axis_angle(Vec3::Z, Vec2::X.angle_between(direction));
+            }
 
-            let velocity = match turret.mode {
-                TurretMode::Fast => 20.0,
-                _ => 10.0,
-            };
-
-            let shots: Vec<f32> = match turret.mode {
-                TurretMode::Shotgun => linspace(-0.2, 0.2, 3).collect(),
-                TurretMode::Split => linspace(-0.2, 0.2, 2).collect(),
-                TurretMode::Nuke => linspace(-PI, PI, 30).collect(),
-                TurretMode::Reverse => vec![-PI],
-                _ => vec![0.0],
-            };
+            let round = turret.round;
+            let velocity = round.speed() * weapon.speed;
+            let shots = movement::fan(weapon.angle, weapon.spread, weapon.count.max(1));
 
             for shot_angle in shots {
                 let mut shot_transform = transform;
@@ -152,36 +474,45 @@ impl Plugin {
                         texture_atlas: assets.bullet.clone(),
                         transform: shot_transform,
                         sprite: TextureAtlasSprite {
-                            custom_size: Some(Vec2::ONE),
+                            custom_size: Some(Vec2::splat(round.scale())),
                             ..Default::default()
                         },
                         ..Default::default()
                     })
                     .insert(Velocity(shot_transform.local_x().truncate() * velocity))
-                    .insert(Bullet::default())
-                    .insert(DespawnTimer::new(Duration::from_secs(5)))
+                    .insert(Bullet {
+                        pierce: round.pierce() + weapon.pierce,
+                        damage: round.damage() * weapon.damage,
+                        hit: Vec::new(),
+                    })
+                    .insert(Collider::Circle {
+                        radius: 0.35 * round.scale(),
+                    })
+                    .insert(Interpolated::default())
+                    .insert(DespawnTimer::new(Duration::from_secs_f32(
+                        weapon.lifetime.max(0.0),
+                    )))
                     .insert(Name::from("Bullet"));
             }
 
-            if turret.mode == TurretMode::Nuke {
-                turret.mode = TurretMode::Base
-            }
+            power_up.spend_shot();
+            shots.send(Shot);
         }
     }
 
     fn aim(
-        cursor: Res<Cursor>,
-        mut turrets: Query<&mut Transform, (With<Turret>, Without<Harvester>)>,
+        controls: Res<Controls>,
+        mut turrets: Query<(&Turret, &mut Transform), Without<Harvester>>,
         combines: Query<&Transform, With<Harvester>>,
     ) {
-        for mut turret_transform in &mut turrets {
-            if let Ok(combine_transform) = combines.get_single() {
+        for (turret, mut turret_transform) in &mut turrets {
+            if let Ok(combine_transform) = combines.get(turret.harvester) {
                 turret_transform.translation = combine_transform.translation + Vec3::Z;
                 turret_transform.translation -= combine_transform.local_x() * 0.15;
             }
 
             let direction =
-                match (**cursor - turret_transform.translation.truncate()).try_normalize() {
+                match (controls.aim - turret_transform.translation.truncate()).try_normalize() {
                     Some(d) => d,
                     None => continue,
                 };
@@ -191,104 +522,187 @@ impl Plugin {
         }
     }
 
+    /// Damages the enemies hit by bullets, killing them once out of hit points
+    #[allow(clippy::too_many_arguments)]
     fn kill_enemy(
         mut commands: Commands,
-        bullets: Query<(Entity, &GlobalTransform), With<Bullet>>,
-        enemies: Query<(Entity, &GlobalTransform, &Transform), With<Enemy>>,
+        mut collisions: EventReader<Collision<Bullet, Enemy>>,
+        mut enemies: Query<(&Transform, &mut Health, &Kind), With<Enemy>>,
+        mut bullets: Query<&mut Bullet>,
         assets: Res<AssetTable>,
+        handle: Res<WeaponsHandle>,
+        weapons: Res<Assets<Weapons>>,
+        mut rng: ResMut<GameRng>,
+        mut kills: EventWriter<EnemyKilled>,
     ) {
-        for (bullet_entity, bullet) in &bullets {
-            for (enemy_entity, enemy, enemy_transform) in &enemies {
-                let dist_squared = (bullet.translation().truncate()
-                    - enemy.translation().truncate())
-                .length_squared();
-                if dist_squared < 0.3 {
-                    let mut rng = thread_rng();
-
-                    if rng.gen_bool(0.1) {
-                        let turret_mode = match rng.gen_range(0..100) {
-                            0..=10 => TurretMode::Base,
-                            11..=40 => TurretMode::Fast,
-                            41..=70 => TurretMode::Shotgun,
-                            71..=89 => TurretMode::Split,
-                            90..=90 => TurretMode::Reverse,
-                            91..=100 => TurretMode::Nuke,
-                            _ => TurretMode::Base,
-                        };
-
-                        commands
-                            .spawn_bundle(SpriteSheetBundle {
-                                texture_atlas: assets.item.clone(),
-                                transform: *enemy_transform,
-                                sprite: TextureAtlasSprite {
-                                    custom_size: Some(Vec2::ONE),
-                                    index: match turret_mode {
-                                        TurretMode::Shotgun => 0,
-                                        TurretMode::Fast => 1,
-                                        TurretMode::Split => 2,
-                                        TurretMode::Nuke => 3,
-                                        TurretMode::Reverse => 4,
-                                        TurretMode::Base => 5,
-                                    },
-                                    ..Default::default()
-                                },
-                                ..Default::default()
-                            })
-                            .insert(Item { mode: turret_mode })
-                            .insert(DespawnTimer::new(Duration::from_secs(5)))
-                            .insert(Name::from("Item"));
-                    }
+        let mut spent = HashSet::new();
+        for collision in collisions.iter() {
+            let (bullet_entity, enemy_entity) = (collision.a, collision.b);
+            if spent.contains(&bullet_entity) {
+                continue;
+            }
+            let (enemy_transform, mut health, kind) = match enemies.get_mut(enemy_entity) {
+                Ok(enemy) if !enemy.1.is_dead() => enemy,
+                _ => continue,
+            };
+            let mut bullet = match bullets.get_mut(bullet_entity) {
+                Ok(bullet) => bullet,
+                Err(_) => continue,
+            };
+            if bullet.hit.contains(&enemy_entity) {
+                continue;
+            }
+            bullet.hit.push(enemy_entity);
+            health.current = health.current.saturating_sub(bullet.damage);
+            if bullet.pierce > 0 {
+                bullet.pierce -= 1;
+            } else {
+                spent.insert(bullet_entity);
+                commands.entity(bullet_entity).despawn_recursive();
+            }
+            if !health.is_dead() {
+                continue;
+            }
 
-                    commands.entity(enemy_entity).despawn_recursive();
-                    commands.entity(bullet_entity).despawn_recursive();
+            let dropped = kind.drop(rng.gen()).and_then(|name| {
+                let weapon = weapons.get(&handle.0)?.get(name);
+                if weapon.is_none() {
+                    error!("Unknown weapon {name}");
                 }
+                Some((name, weapon?))
+            });
+            if let Some((name, weapon)) = dropped {
+                commands
+                    .spawn_bundle(SpriteSheetBundle {
+                        texture_atlas: assets.item.clone(),
+                        transform: *enemy_transform,
+                        sprite: TextureAtlasSprite {
+                            custom_size: Some(Vec2::ONE),
+                            index: weapon.sprite,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .insert(Item {
+                        weapon: name.to_string(),
+                    })
+                    .insert(Collider::Circle { radius: 0.4 })
+                    .insert(DespawnTimer::new(Duration::from_secs(5)))
+                    .insert(Name::from("Item"));
+            }
+
+            commands.entity(enemy_entity).despawn_recursive();
+            kills.send(EnemyKilled {
+                position: enemy_transform.translation.truncate(),
+                kind: kind.clone(),
+            });
+        }
+    }
+
+    /// Destroys the enemy projectiles hit by bullets, which are spent unless piercing
+    fn shoot_down(
+        mut commands: Commands,
+        mut collisions: EventReader<Collision<Bullet, Projectile>>,
+        mut bullets: Query<&mut Bullet>,
+    ) {
+        let mut destroyed = HashSet::new();
+        for collision in collisions.iter() {
+            let (bullet_entity, projectile) = (collision.a, collision.b);
+            let mut bullet = match bullets.get_mut(bullet_entity) {
+                Ok(bullet) => bullet,
+                Err(_) => continue,
+            };
+            if destroyed.contains(&bullet_entity) || !destroyed.insert(projectile) {
+                continue;
+            }
+            commands.entity(projectile).despawn_recursive();
+            if bullet.pierce > 0 {
+                bullet.pierce -= 1;
+            } else {
+                destroyed.insert(bullet_entity);
+                commands.entity(bullet_entity).despawn_recursive();
+            }
+        }
+    }
+
+    fn hit_obstacle(
+        mut commands: Commands,
+        mut collisions: EventReader<Collision<Bullet, Cell>>,
+        cells: Query<&Cell>,
+    ) {
+        let mut stopped = HashSet::new();
+        for collision in collisions.iter() {
+            let obstacle = cells.get(collision.b).map_or(false, Cell::is_obstacle);
+            if obstacle && stopped.insert(collision.a) {
+                commands.entity(collision.a).despawn_recursive();
             }
         }
     }
 
     fn collect_item(
         mut commands: Commands,
-        combines: Query<&GlobalTransform, (With<Harvester>, Without<Item>)>,
-        items: Query<(Entity, &Item, &GlobalTransform)>,
-        mut turrets: Query<&mut Turret>,
+        mut collisions: EventReader<Collision<Harvester, Item>>,
+        items: Query<&Item>,
+        handle: Res<WeaponsHandle>,
+        weapons: Res<Assets<Weapons>>,
+        mut power_ups: Query<&mut PowerUp>,
+        mut collected: EventWriter<ItemCollected>,
     ) {
-        for combine in &combines {
-            for (item_entity, item, item_transform) in &items {
-                let dist_squared = (combine.translation().truncate()
-                    - item_transform.translation().truncate())
-                .length_squared();
-
-                if dist_squared < 0.3 {
-                    for mut turret in &mut turrets {
-                        turret.mode = item.mode
+        let weapons = match weapons.get(&handle.0) {
+            Some(weapons) => weapons,
+            None => return,
+        };
+        for collision in collisions.iter() {
+            if let Ok(item) = items.get(collision.b) {
+                if let Some(weapon) = weapons.get(&item.weapon) {
+                    for mut power_up in &mut power_ups {
+                        power_up.collect(&item.weapon, weapon);
                     }
-
-                    commands.entity(item_entity).despawn_recursive();
                 }
+
+                commands.entity(collision.b).despawn_recursive();
+                collected.send(ItemCollected);
             }
         }
     }
 
-    fn spawn_turret(mut commands: Commands, assets: Res<AssetTable>) {
-        commands
-            .spawn_bundle(SpriteSheetBundle {
-                texture_atlas: assets.turret.clone(),
-                sprite: TextureAtlasSprite {
-                    custom_size: Some(Vec2::ONE),
+    /// Mounts a turret on each harvester
+    fn spawn_turret(
+        mut commands: Commands,
+        assets: Res<AssetTable>,
+        combines: Query<Entity, With<Harvester>>,
+    ) {
+        for harvester in &combines {
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas: assets.turret.clone(),
+                    sprite: TextureAtlasSprite {
+                        custom_size: Some(Vec2::ONE),
+                        ..Default::default()
+                    },
                     ..Default::default()
-                },
-                ..Default::default()
-            })
-            .insert(Turret::default())
-            .insert(Name::from("Turret"))
-            .insert(Ammo::default());
+                })
+                .insert(Turret::new(harvester))
+                .insert(PowerUp::default())
+                .insert(Interpolated::default())
+                .insert(Name::from("Turret"))
+                .insert(Ammo::default());
+        }
+    }
+
+    fn load_weapons(mut handle: ResMut<WeaponsHandle>, server: Res<AssetServer>) {
+        handle.0 = server.load("balance/default.weapons.ron");
     }
 
     fn load_assets(
         mut table: ResMut<AssetTable>,
         server: Res<AssetServer>,
-        mut textures: ResMut<Assets<TextureAtlas>>,
+        textures: Option<ResMut<Assets<TextureAtlas>>>,
     ) {
+        let mut textures = match textures {
+            Some(textures) => textures,
+            None => return,
+        };
         table.turret = textures.add(TextureAtlas::from_grid(
             server.load("sprites/turret.png"),
             Vec2::splat(32.0),
@@ -304,8 +718,68 @@ impl Plugin {
         table.item = textures.add(TextureAtlas::from_grid(
             server.load("sprites/item.png"),
             Vec2::splat(32.0),
-            6,
+            ITEM_COLUMNS,
             1,
         ));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_the_last_harvested_round_first() {
+        let mut ammo = Ammo::default();
+        ammo.load(Round::Regular);
+        ammo.load(Round::Piercing);
+        assert_eq!(ammo.loaded(), Some(Round::Piercing));
+        for _ in 0..MAX_AMMO {
+            ammo.load(Round::Heavy);
+        }
+        assert_eq!(ammo.count(), MAX_AMMO);
+        assert_eq!(ammo.loaded(), Some(Round::Heavy));
+    }
+
+    #[test]
+    fn power_ups_run_out_then_fall_back_to_the_base_weapon() {
+        let weapons: Weapons = ron::from_str(
+            r#"(base: "base", weapons: {
+                "base": (),
+                "shotgun": (count: 3, spread: 0.4, budget: Time(12.0)),
+                "nuke": (count: 30, spread: 6.28, budget: Shots(1), stacks: true),
+            })"#,
+        )
+        .unwrap();
+        let shotgun = weapons.get("shotgun").unwrap();
+        let nuke = weapons.get("nuke").unwrap();
+
+        let mut power_up = PowerUp::default();
+        power_up.collect("shotgun", shotgun);
+        power_up.spend_time(10.0);
+        power_up.collect("shotgun", shotgun);
+        assert_eq!(power_up.charge, shotgun.budget.charge());
+        power_up.spend_time(12.0);
+        assert_eq!(power_up, PowerUp::default());
+
+        power_up.collect("nuke", nuke);
+        power_up.collect("nuke", nuke);
+        power_up.spend_shot();
+        assert_eq!(power_up.weapon.as_deref(), Some("nuke"));
+        assert_eq!(power_up.charge.fraction(), 0.5);
+        power_up.spend_shot();
+        assert_eq!(power_up, PowerUp::default());
+        assert_eq!(weapons.active(&power_up).unwrap().count, 1);
+    }
+
+    #[test]
+    fn default_weapons_are_consistent() {
+        let weapons: Weapons =
+            ron::from_str(include_str!("../assets/balance/default.weapons.ron")).unwrap();
+        assert!(weapons.get(&weapons.base).is_some());
+        for weapon in weapons.weapons.values() {
+            assert!(weapon.sprite < ITEM_COLUMNS);
+            assert!(weapon.count > 0);
+        }
+    }
+}
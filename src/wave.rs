@@ -0,0 +1,279 @@
+use std::{iter, time::Duration};
+
+use bevy::{prelude::*, reflect::TypeUuid};
+use iyes_loopless::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+
+use crate::{
+    data::RonAssetApp,
+    difficulty::Difficulty,
+    enemy::{Catalogue, CatalogueHandle, Enemy, EnemyKilled, SpawnEnemy},
+    field::Field,
+    level::Edge,
+    rng::GameRng,
+    simulation, GameState,
+};
+
+/// Calm time before the first wave
+const FIRST_WAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// How the enemies of a wave come in, by groups of the spawn count of the difficulty curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Pattern {
+    /// Each enemy from a random spawn edge
+    Scattered,
+    /// Each group from a single random spawn edge
+    Flank,
+    /// Each group spread over all the spawn edges
+    Surround,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Wave {
+    /// Kinds of enemies, from the enemy catalogue, with their number
+    enemies: Vec<(String, u32)>,
+    pattern: Pattern,
+    /// Added to the score when the wave is cleared
+    bonus: f32,
+}
+
+/// Waves of enemies, loaded from `assets/balance/default.waves.ron`
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "a4f2d1c7-3e58-4b9a-8c61-0d7e5f2b9c48"]
+pub struct Waves {
+    /// Calm time between two waves, in seconds
+    intermission: f32,
+    waves: Vec<Wave>,
+    /// Additional enemies in each wave after the last one, whose kinds are picked from the
+    /// catalogue
+    endless_growth: u32,
+}
+
+impl Waves {
+    /// Wave number `number` (from 1), made up after the last one
+    fn wave(
+        &self,
+        number: u32,
+        catalogue: &Catalogue,
+        intensity: f32,
+        rng: &mut GameRng,
+    ) -> Option<Wave> {
+        if let Some(wave) = self.waves.get(number as usize - 1) {
+            return Some(wave.clone());
+        }
+        let last = self.waves.last()?;
+        let extra = number - self.waves.len() as u32;
+        let count = last.enemies.iter().map(|(_, n)| n).sum::<u32>() + extra * self.endless_growth;
+        let enemies = (0..count)
+            .filter_map(|_| catalogue.pick(intensity, rng.gen()))
+            .map(|name| (name.to_string(), 1))
+            .collect();
+        Some(Wave {
+            enemies,
+            pattern: last.pattern,
+            bonus: last.bonus * (1.0 + 0.25 * extra as f32),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct WavesHandle(Handle<Waves>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Calm time before the next wave
+    Intermission,
+    /// Spawning the enemies of the wave
+    Spawning,
+    /// Waiting for the enemies of the wave to be cleared
+    Fighting,
+}
+
+/// Progress through the waves of the current run
+#[derive(Debug, Clone)]
+pub struct Director {
+    /// Number of the current wave (from 1), 0 before the first one
+    pub wave: u32,
+    pub phase: Phase,
+    /// Number of enemies spawned during the current wave
+    pub spawned: u32,
+    pattern: Pattern,
+    bonus: f32,
+    /// Kinds of the enemies left to spawn, the next one last
+    queue: Vec<String>,
+    timer: Timer,
+}
+
+impl Default for Director {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            phase: Phase::Intermission,
+            spawned: 0,
+            pattern: Pattern::Scattered,
+            bonus: 0.0,
+            queue: Vec::new(),
+            timer: Timer::new(FIRST_WAVE_DELAY, false),
+        }
+    }
+}
+
+pub struct WaveStarted {
+    pub number: u32,
+}
+
+pub struct WaveCleared {
+    pub number: u32,
+    pub bonus: f32,
+}
+
+#[derive(Default)]
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<Waves>(&["waves.ron"])
+            .init_resource::<Director>()
+            .init_resource::<WavesHandle>()
+            .add_event::<WaveStarted>()
+            .add_event::<WaveCleared>()
+            .add_startup_system(Self::load_waves)
+            .add_enter_system(GameState::Ready, Self::reset)
+            .add_fixed_timestep_system(
+                simulation::LABEL,
+                simulation::UPDATE,
+                Self::direct.run_in_state(GameState::Playing),
+            );
+    }
+}
+
+impl Plugin {
+    fn reset(mut commands: Commands) {
+        commands.insert_resource(Director::default());
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn direct(
+        mut director: ResMut<Director>,
+        handle: Res<WavesHandle>,
+        waves: Res<Assets<Waves>>,
+        catalogue_handle: Res<CatalogueHandle>,
+        catalogues: Res<Assets<Catalogue>>,
+        difficulty: Res<Difficulty>,
+        field: Res<Field>,
+        enemies: Query<(), With<Enemy>>,
+        mut kills: EventReader<EnemyKilled>,
+        mut rng: ResMut<GameRng>,
+        mut spawns: EventWriter<SpawnEnemy>,
+        mut started: EventWriter<WaveStarted>,
+        mut cleared: EventWriter<WaveCleared>,
+    ) {
+        let (waves, catalogue) = match (waves.get(&handle.0), catalogues.get(&catalogue_handle.0)) {
+            (Some(waves), Some(catalogue)) => (waves, catalogue),
+            _ => return,
+        };
+        director.timer.tick(simulation::STEP);
+        // The enemies coming out of a killed one are not spawned yet
+        let splitting = kills.iter().any(|killed| killed.kind.splits());
+
+        match director.phase {
+            Phase::Intermission if director.timer.finished() => {
+                let number = director.wave + 1;
+                let wave = match waves.wave(number, catalogue, difficulty.intensity, &mut rng) {
+                    Some(wave) => wave,
+                    None => return,
+                };
+                let mut queue: Vec<String> = wave
+                    .enemies
+                    .iter()
+                    .flat_map(|(kind, count)| iter::repeat(kind.clone()).take(*count as usize))
+                    .collect();
+                queue.shuffle(&mut **rng);
+                *director = Director {
+                    wave: number,
+                    phase: Phase::Spawning,
+                    spawned: 0,
+                    pattern: wave.pattern,
+                    bonus: wave.bonus,
+                    queue,
+                    timer: Timer::new(difficulty.spawn_interval, true),
+                };
+                started.send(WaveStarted { number });
+            }
+            Phase::Intermission => {}
+            Phase::Spawning if director.timer.just_finished() => {
+                let group = difficulty.spawn_count.min(director.queue.len() as u32);
+                let mut edge = Self::random_edge(&field, &mut rng);
+                for _ in 0..group {
+                    match director.pattern {
+                        Pattern::Scattered => edge = Self::random_edge(&field, &mut rng),
+                        Pattern::Flank => {}
+                        Pattern::Surround => {
+                            let index = director.spawned as usize % field.spawn_edges.len();
+                            edge = field.spawn_edges[index];
+                        }
+                    }
+                    let position = Self::position_on(edge, &field, &mut rng);
+                    if let Some(kind) = director.queue.pop() {
+                        spawns.send(SpawnEnemy { kind, position });
+                        director.spawned += 1;
+                    }
+                }
+                if director.queue.is_empty() {
+                    director.phase = Phase::Fighting;
+                } else if director.timer.duration() != difficulty.spawn_interval {
+                    director.timer.set_duration(difficulty.spawn_interval);
+                }
+            }
+            Phase::Spawning => {}
+            Phase::Fighting if enemies.is_empty() && !splitting => {
+                cleared.send(WaveCleared {
+                    number: director.wave,
+                    bonus: director.bonus,
+                });
+                director.phase = Phase::Intermission;
+                director.timer = Timer::new(Duration::from_secs_f32(waves.intermission), false);
+            }
+            Phase::Fighting => {}
+        }
+    }
+
+    fn random_edge(field: &Field, rng: &mut GameRng) -> Edge {
+        field.spawn_edges[rng.gen_range(0..field.spawn_edges.len())]
+    }
+
+    /// Random position just outside of the field, along `edge`
+    fn position_on(edge: Edge, field: &Field, rng: &mut GameRng) -> Vec2 {
+        let position = match edge {
+            Edge::Left => IVec2::new(-1, rng.gen_range(0..field.height) as i32),
+            Edge::Right => IVec2::new(field.width as i32, rng.gen_range(0..field.height) as i32),
+            Edge::Bottom => IVec2::new(rng.gen_range(0..field.width) as i32, -1),
+            Edge::Top => IVec2::new(rng.gen_range(0..field.width) as i32, field.height as i32),
+        };
+        position.as_vec2()
+    }
+
+    fn load_waves(mut handle: ResMut<WavesHandle>, server: Res<AssetServer>) {
+        handle.0 = server.load("balance/default.waves.ron");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_waves_only_use_kinds_of_the_catalogue() {
+        let waves: Waves =
+            ron::from_str(include_str!("../assets/balance/default.waves.ron")).unwrap();
+        let catalogue: Catalogue =
+            ron::from_str(include_str!("../assets/balance/default.enemies.ron")).unwrap();
+        assert!(!waves.waves.is_empty());
+        for wave in &waves.waves {
+            for (kind, _) in &wave.enemies {
+                assert!(catalogue.get(kind).is_some(), "unknown kind {kind}");
+            }
+        }
+    }
+}